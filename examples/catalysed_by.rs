@@ -0,0 +1,34 @@
+use anyhow::{Context, Result};
+use research_assistant::{
+    config::Config, inherit::resolve_inheritance, loader::Loader, xtrigger::catalysed_by,
+};
+use std::env::args;
+
+/// Usage: `catalysed_by <catalyst>`, for example `catalysed_by reading`
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let catalyst = args().nth(1).context("expected a catalyst")?;
+
+    let config = Config::read_config()?.resolve()?;
+    let mut compendium = Loader::new().load(&config)?;
+    resolve_inheritance(&mut compendium);
+
+    for (element, transformations) in catalysed_by(&compendium, &catalyst) {
+        println!("{}", element.id);
+        for transformation in transformations {
+            println!("    {transformation}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::{bail, Result};
+use research_assistant::config::ConfigLayers;
+use std::{env::args, path::PathBuf};
+
+/// Usage: `config explain [--config <path>] [--json]`
+///
+/// Shows every value of the merged config, with the layer (and file) it came from.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut json = false;
+    let mut explicit = None;
+    let mut args = args().skip(1);
+    match args.next().as_deref() {
+        Some("explain") => (),
+        Some(command) => bail!("unknown command {command:?}"),
+        None => bail!("expected a command, like explain"),
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--config" => match args.next() {
+                Some(path) => explicit = Some(PathBuf::from(path)),
+                None => bail!("expected a path after --config"),
+            },
+            _ => bail!("unknown argument {arg:?}"),
+        }
+    }
+
+    let layers = ConfigLayers::discover(explicit.as_deref())?;
+    let explanation = layers.explain();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&explanation)?);
+        return Ok(());
+    }
+    for file in &layers.files {
+        println!("# {}: {}", file.layer, file.path.display());
+    }
+    print!("{explanation}");
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use anyhow::{bail, Result};
+use research_assistant::{
+    config::Config,
+    deck::{recipe_draws, CardOdds, Deck},
+    inherit::resolve_inheritance,
+    loader::Loader,
+};
+use std::{collections::BTreeMap, env::args};
+
+/// Usage: `deck_odds <deck or recipe id> [draws]`
+///
+/// For a deck, `draws` defaults to `1`, for a recipe the draws of its `deckeffects` and internal
+/// deck are used, where the internal deck is simulated.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut args = args().skip(1);
+    let Some(id) = args.next() else {
+        bail!("expected a deck or recipe id");
+    };
+    let draws = args.next().map(|draws| draws.parse()).transpose()?;
+
+    let config = Config::read_config()?.resolve()?;
+    let mut compendium = Loader::new().load(&config)?;
+    resolve_inheritance(&mut compendium);
+
+    if let Some(deck) = compendium.decks.get(&id) {
+        let draws = draws.unwrap_or(1);
+        println!("{id} x{draws}");
+        print_odds(&Deck::from(&**deck).odds(draws));
+    } else if let Some(recipe) = compendium.recipes.get(&id) {
+        let recipe_draws = recipe_draws(&compendium, recipe);
+        for id in &recipe_draws.missing {
+            eprintln!("Err no deck {id:?}");
+        }
+        for (deck, deck_draws) in recipe_draws.decks {
+            let draws = draws.unwrap_or(deck_draws);
+            match &deck.id {
+                Some(deck_id) => {
+                    println!("{deck_id} x{draws}");
+                    print_odds(&deck.odds(draws));
+                }
+                None => {
+                    println!("internal deck x{draws}");
+                    print_odds(&deck.monte_carlo(draws, 100_000, 0));
+                }
+            }
+        }
+    } else {
+        bail!("no deck or recipe {id:?}");
+    }
+    Ok(())
+}
+
+fn print_odds(odds: &BTreeMap<String, CardOdds>) {
+    for (card, odds) in odds {
+        println!(
+            "    {card}: {:.2}% at least once, {:.3} expected",
+            odds.at_least_once * 100.0,
+            odds.expected
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use research_assistant::{config::Config, diagnostic::Diagnostic, loader::Loader, reader::Reader};
+use std::env::args;
+
+/// Usage: `diagnose [--json]`
+///
+/// Loads every source and describes the first file that fails to load.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let json = args().skip(1).any(|arg| arg == "--json");
+
+    let config = Config::read_config()?.resolve()?;
+    let Err(err) = Loader::new().load(&config) else {
+        println!("everything loaded");
+        return Ok(());
+    };
+
+    let diagnostic = Diagnostic::from_load_error(&err, &config, &mut Reader::new());
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&diagnostic)?),
+        false => print!("{diagnostic}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anyhow::{bail, Result};
+use research_assistant::{
+    config::Config, diff::ContentDiff, inherit::resolve_inheritance, loader::Loader,
+};
+use std::env::args;
+
+/// Usage: `diff <old config.toml> <new config.toml> [--json]`
+///
+/// Loads the content of both configs, like two versions of the game, and lists every entity that
+/// was added, removed or changed.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut json = false;
+    let mut configs = Vec::new();
+    for arg in args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ => configs.push(arg),
+        }
+    }
+    let [old, new] = configs.as_slice() else {
+        bail!("expected an old and a new config, got {configs:?}");
+    };
+
+    let old = Config::read_config_from_path(old)?.resolve()?;
+    let new = Config::read_config_from_path(new)?.resolve()?;
+    let mut old = Loader::new().load(&old)?;
+    let mut new = Loader::new().load(&new)?;
+    resolve_inheritance(&mut old);
+    resolve_inheritance(&mut new);
+
+    let diff = ContentDiff::new(&old, &new);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+    print!("{diff}");
+    let (added, removed, changed) = diff.counts();
+    println!("{added} added, {removed} removed, {changed} changed");
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use anyhow::{bail, Result};
+use research_assistant::discover::{find_installs, SearchPaths};
+use std::env::args;
+
+/// Usage: `discover_installs [--home <dir>] [--json]`
+///
+/// Lists every Book of Hours install that can be found, and the sources `root = "auto"` would
+/// load from the first one. `--home` searches a fixture directory instead of the real home.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut json = false;
+    let mut home = None;
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--home" => match args.next() {
+                Some(dir) => home = Some(dir),
+                None => bail!("expected a directory after --home"),
+            },
+            _ => bail!("unknown argument {arg:?}"),
+        }
+    }
+    let paths = match home {
+        Some(home) => SearchPaths::with_home(home),
+        None => match SearchPaths::from_env() {
+            Some(paths) => paths,
+            None => bail!("$HOME isn't set"),
+        },
+    };
+
+    let installs = find_installs(&paths);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&installs)?);
+        return Ok(());
+    }
+    if installs.is_empty() {
+        println!("no installs found");
+        return Ok(());
+    }
+    for install in &installs {
+        println!("{}: {}", install.store, install.path.display());
+        if let Some(prefix) = &install.prefix {
+            println!("    prefix: {}", prefix.display());
+        }
+        if let Some(user_data) = &install.user_data {
+            println!("    user data: {}", user_data.display());
+        }
+        if let Some(workshop) = &install.workshop {
+            println!("    workshop: {}", workshop.display());
+        }
+    }
+
+    println!();
+    println!("root = \"auto\" loads:");
+    for source in installs[0].source_configs() {
+        println!("    {}", source.root.display());
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
-use anyhow::{Context, Result};
-use research_assistant::{config::Config, reader::Reader};
+use anyhow::Result;
+use research_assistant::{config::Config, loader::Loader};
 
 pub fn main() -> Result<()> {
     match run() {
@@ -15,15 +15,12 @@ pub fn main() -> Result<()> {
 
 fn run() -> Result<()> {
     let config = Config::read_config()?.resolve()?;
-    let mut reader = Reader::new();
+    let mut loader = Loader::new();
     let mut sources = Vec::with_capacity(config.source.len());
-    for source in config.source {
-        for file in source.source_files() {
-            let (entry, format) = file?;
-            let v: serde_json::Value = reader
-                .deserialize_from(entry.path(), format)
-                .with_context(|| entry.path().display().to_string())?;
-            sources.push(v);
+    for file in loader.read_files::<serde_json::Value>(&config) {
+        match file {
+            Ok((_, v)) => sources.push(v),
+            Err(err) => eprintln!("Err {err}"),
         }
     }
     println!("{}", serde_json::to_string(&sources)?);
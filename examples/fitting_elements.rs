@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use research_assistant::{
+    config::Config, data::EntityKind, inherit::resolve_inheritance, loader::Loader,
+    slots::entity_slots,
+};
+use std::env::args;
+
+/// Usage: `fitting_elements <elements|recipes|verbs> <id> [slot id]`
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut args = args().skip(1);
+    let kind: EntityKind = serde_json::from_value(args.next().context("expected a kind")?.into())?;
+    let id = args.next().context("expected an id")?;
+    let slot_id = args.next();
+
+    let config = Config::read_config()?.resolve()?;
+    let mut compendium = Loader::new().load(&config)?;
+    resolve_inheritance(&mut compendium);
+
+    let slots = entity_slots(&compendium, kind, &id).context("no such entity with slots")?;
+    for slot in slots.iter().filter(|slot| match &slot_id {
+        Some(id) => *id == slot.id,
+        None => true,
+    }) {
+        match serde_json::to_string(&slot.if_aspects_present) {
+            Ok(condition) if !slot.if_aspects_present.is_empty() => {
+                println!("{}  # if {condition}", slot.id)
+            }
+            _ => println!("{}", slot.id),
+        }
+        for element in slot.fitting_elements(&compendium) {
+            println!("    {}", element.id);
+        }
+    }
+    Ok(())
+}
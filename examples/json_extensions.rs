@@ -0,0 +1,63 @@
+use anyhow::Result;
+use research_assistant::{
+    config::{strip_root, Config, SourceEntry},
+    reader::Reader,
+    strict::{find_extensions, Extension},
+};
+use std::collections::BTreeMap;
+
+/// Usage: `json_extensions`
+///
+/// Lists every source file that uses extensions to standard JSON, and which ones it uses.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let config = Config::read_config()?.resolve()?;
+    let mut reader = Reader::new();
+    let (mut files, mut non_standard) = (0, 0);
+    for source in &config.source {
+        for file in source.source_files() {
+            let SourceEntry { entry, format, .. } = file?;
+            let text = match reader.read_to_string(entry.path(), format) {
+                Ok(text) => text,
+                Err(err) => {
+                    eprintln!("Err {}: {err}", entry.path().display());
+                    continue;
+                }
+            };
+            files += 1;
+
+            let uses = find_extensions(&text);
+            let Some(first) = uses.first() else {
+                continue;
+            };
+            non_standard += 1;
+            let mut counts: BTreeMap<Extension, usize> = BTreeMap::new();
+            for extension_use in &uses {
+                *counts.entry(extension_use.extension).or_default() += 1;
+            }
+            let counts: Vec<_> = counts
+                .into_iter()
+                .map(|(extension, count)| format!("{count}x {extension}"))
+                .collect();
+            println!(
+                "{}: {} (first at {})",
+                strip_root(&source.root, &entry).display(),
+                counts.join(", "),
+                first.span,
+            );
+        }
+    }
+    println!("{non_standard} of {files} files aren't standard JSON");
+    Ok(())
+}
@@ -57,6 +57,15 @@ struct Sources {
 struct Source {
     source: String,
     format: ResolvedSourceFormat,
+    /// The glob of the `files` rule the format is from, [`None`] for the source's `format`.
+    rule: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PickedFormat<'a> {
+    rule: &'a Option<String>,
+    #[serde(flatten)]
+    format: &'a ResolvedSourceFormat,
 }
 
 impl Serialize for Source {
@@ -65,7 +74,13 @@ impl Serialize for Source {
         S: serde::Serializer,
     {
         let mut map = serializer.serialize_map(Some(1))?;
-        map.serialize_entry(&self.source, &self.format)?;
+        map.serialize_entry(
+            &self.source,
+            &PickedFormat {
+                rule: &self.rule,
+                format: &self.format,
+            },
+        )?;
         map.end()
     }
 }
@@ -82,9 +97,14 @@ fn run() -> anyhow::Result<String> {
                     .source_files()
                     .map(|files| {
                         files
-                            .map(|(file, config)| Source {
-                                source: strip_root(&source.root, &file).display().to_string(),
-                                format: config,
+                            .map(|file| {
+                                let path = strip_root(&source.root, &file.entry);
+                                Source {
+                                    source: path.display().to_string(),
+                                    format: file.format,
+                                    rule: (source.files.rule_of(path))
+                                        .map(|rule| rule.glob.glob().glob().to_owned()),
+                                }
                             })
                             .into()
                     })
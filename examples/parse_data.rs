@@ -1,5 +1,5 @@
-use anyhow::{Context, Result};
-use research_assistant::{config::Config, data::Data, reader::Reader};
+use anyhow::Result;
+use research_assistant::{config::Config, diagnostic::Diagnostic, loader::Loader, reader::Reader};
 
 pub fn main() -> Result<()> {
     match run() {
@@ -15,17 +15,12 @@ pub fn main() -> Result<()> {
 
 fn run() -> Result<()> {
     let config = Config::read_config()?.resolve()?;
+    let (compendium, errors) = Loader::new().load_partial(&config);
     let mut reader = Reader::new();
-    let mut sources = Vec::with_capacity(config.source.len());
-    for source in config.source {
-        for file in source.source_files() {
-            let (entry, format) = file?;
-            let data: Data = reader
-                .deserialize_from(entry.path(), format)
-                .with_context(|| entry.path().display().to_string())?;
-            sources.push(data);
-        }
+    for err in &errors {
+        eprintln!("{}", Diagnostic::from_load_error(err, &config, &mut reader));
     }
-    println!("{}", serde_json::to_string(&sources)?);
+    eprintln!("{} errors", errors.len());
+    println!("{}", serde_json::to_string(&compendium)?);
     Ok(())
 }
@@ -0,0 +1,71 @@
+use anyhow::{bail, Result};
+use research_assistant::{
+    config::Config,
+    inherit::resolve_inheritance,
+    loader::Loader,
+    plan::{plan, PlanOptions},
+    simulate::element_aspects,
+};
+use std::env::args;
+
+/// Usage: `plan <target> [--have <element>]... [--steps <n>] [--plans <n>]`
+///
+/// Prints the ways to produce `target`, where the elements of `--have` are already at hand.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut args = args().skip(1);
+    let Some(target) = args.next() else {
+        bail!("expected a target element or aspect");
+    };
+
+    let config = Config::read_config()?.resolve()?;
+    let mut compendium = Loader::new().load(&config)?;
+    resolve_inheritance(&mut compendium);
+
+    let mut options = PlanOptions::default();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            bail!("expected a value after {arg:?}");
+        };
+        match arg.as_str() {
+            "--have" => {
+                let Some(element) = compendium.elements.get(&value) else {
+                    bail!("no element {value:?}");
+                };
+                for (aspect, amount) in element_aspects(element) {
+                    *options.available.entry(aspect).or_default() += amount;
+                }
+            }
+            "--steps" => options.max_steps = value.parse()?,
+            "--plans" => options.max_plans = value.parse()?,
+            _ => bail!("unknown argument {arg:?}"),
+        }
+    }
+
+    for (i, plan) in plan(&compendium, &target, &options).iter().enumerate() {
+        println!("plan {}: {}s warmup", i + 1, plan.warmup);
+        let verbs: Vec<&str> = plan.verbs.iter().copied().collect();
+        println!("    verbs: {}", verbs.join(", "));
+        let inputs: Vec<String> = plan
+            .inputs
+            .iter()
+            .map(|(input, amount)| format!("{input} x{amount}"))
+            .collect();
+        println!("    inputs: {}", inputs.join(", "));
+        for step in &plan.steps {
+            println!("    {step}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use research_assistant::{
+    config::Config,
+    graph::{GraphFilter, RecipeGraph, Root},
+    inherit::resolve_inheritance,
+    loader::Loader,
+};
+use std::env::args;
+
+/// Usage: `recipe_graph <dot|graphml|json> [--recipe <id>]... [--verb <id>]... [--depth <n>]`
+///
+/// Without `--recipe` or `--verb` the graph of every recipe is printed.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut args = args().skip(1);
+    let Some(format) = args.next() else {
+        bail!("expected a format, one of dot, graphml or json");
+    };
+
+    let mut filter = GraphFilter::default();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            bail!("expected a value after {arg:?}");
+        };
+        match arg.as_str() {
+            "--recipe" => filter.roots.push(Root::Recipe(value)),
+            "--verb" => filter.roots.push(Root::Verb(value)),
+            "--depth" => filter.max_depth = Some(value.parse()?),
+            _ => bail!("unknown argument {arg:?}"),
+        }
+    }
+
+    let config = Config::read_config()?.resolve()?;
+    let mut compendium = Loader::new().load(&config)?;
+    resolve_inheritance(&mut compendium);
+
+    let graph = RecipeGraph::build(&compendium, &filter);
+    match format.as_str() {
+        "dot" => print!("{}", graph.to_dot()),
+        "graphml" => print!("{}", graph.to_graphml()),
+        "json" => println!("{}", graph.to_json()?),
+        _ => bail!("unknown format {format:?}, expected one of dot, graphml or json"),
+    }
+    Ok(())
+}
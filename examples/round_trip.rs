@@ -0,0 +1,176 @@
+use anyhow::{bail, Result};
+use research_assistant::{
+    config::Config,
+    data::{
+        Achievements, Cultures, Decks, Dicta, Elements, Endings, Entity, EntityKind, Legacies,
+        Levers, Portals, Recipes, Settings, Verbs,
+    },
+    loader::Loader,
+    writer::{write_file, KeyLayout},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env::args,
+    fs::{create_dir_all, File},
+    io::BufWriter,
+    path::PathBuf,
+};
+
+/// Usage: `round_trip [--out <dir>]`
+///
+/// Reads every entity of every source, writes it back with its [`KeyLayout`] and checks that the
+/// written entity has the same keys and deserializes to the same value. With `--out` the written
+/// files are stored in `<dir>`, at the same paths as in their source.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+enum Outcome {
+    Written(Value),
+    /// The entity doesn't match the data model, so there is nothing to write.
+    Skipped(String),
+    Failed(String),
+}
+
+fn run() -> Result<()> {
+    let mut out = None;
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => match args.next() {
+                Some(dir) => out = Some(PathBuf::from(dir)),
+                None => bail!("--out needs a directory"),
+            },
+            _ => bail!("unknown argument {arg:?}"),
+        }
+    }
+
+    let config = Config::read_config()?.resolve()?;
+    let mut loader = Loader::new();
+    let (mut written, mut skipped, mut failed) = (0, 0, 0);
+    for file in loader.read_files::<Map<String, Value>>(&config) {
+        let (source, data) = match file {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Err {err}");
+                continue;
+            }
+        };
+
+        let mut entities: BTreeMap<EntityKind, Vec<Value>> = BTreeMap::new();
+        for (key, values) in data {
+            let (Ok(kind), Value::Array(values)) = (key.parse::<EntityKind>(), values) else {
+                continue;
+            };
+            for (i, value) in values.iter().enumerate() {
+                match round_trip(kind, value) {
+                    Outcome::Written(value) => {
+                        written += 1;
+                        entities.entry(kind).or_default().push(value);
+                    }
+                    Outcome::Skipped(reason) => {
+                        skipped += 1;
+                        println!("skipped {source}: {kind}[{i}]: {reason}");
+                    }
+                    Outcome::Failed(reason) => {
+                        failed += 1;
+                        println!("failed {source}: {kind}[{i}]: {reason}");
+                    }
+                }
+            }
+        }
+
+        if let Some(out) = &out {
+            let path = out.join(&source.path);
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent)?;
+            }
+            write_file(BufWriter::new(File::create(path)?), &entities)?;
+        }
+    }
+    println!("{written} written, {skipped} skipped, {failed} failed");
+    Ok(())
+}
+
+fn round_trip(kind: EntityKind, value: &Value) -> Outcome {
+    match kind {
+        EntityKind::Achievements => round_trip_as::<Achievements>(value),
+        EntityKind::Cultures => round_trip_as::<Cultures>(value),
+        EntityKind::Decks => round_trip_as::<Decks>(value),
+        EntityKind::Dicta => round_trip_as::<Dicta>(value),
+        EntityKind::Elements => round_trip_as::<Elements>(value),
+        EntityKind::Endings => round_trip_as::<Endings>(value),
+        EntityKind::Legacies => round_trip_as::<Legacies>(value),
+        EntityKind::Levers => round_trip_as::<Levers>(value),
+        EntityKind::Portals => round_trip_as::<Portals>(value),
+        EntityKind::Recipes => round_trip_as::<Recipes>(value),
+        EntityKind::Settings => round_trip_as::<Settings>(value),
+        EntityKind::Verbs => round_trip_as::<Verbs>(value),
+    }
+}
+
+fn round_trip_as<T: Entity + DeserializeOwned + Serialize>(entity: &Value) -> Outcome {
+    let (value, layout) = match KeyLayout::read::<T>(entity) {
+        Ok(read) => read,
+        Err(err) => return Outcome::Skipped(err.to_string()),
+    };
+    let written = match layout.write(&value) {
+        Ok(written) => written,
+        Err(err) => return Outcome::Failed(err.to_string()),
+    };
+
+    match KeyLayout::read::<T>(&written) {
+        Ok((reread, _)) => {
+            if serde_json::to_value(&reread).ok() != serde_json::to_value(&value).ok() {
+                return Outcome::Failed("the written entity has a different value".to_owned());
+            }
+        }
+        Err(err) => return Outcome::Failed(format!("the written entity doesn't load: {err}")),
+    }
+
+    let (before, after) = (key_paths(entity), key_paths(&written));
+    if before != after {
+        let missing: Vec<_> = before.difference(&after).collect();
+        let added: Vec<_> = after.difference(&before).collect();
+        return Outcome::Failed(format!("keys differ, missing {missing:?}, added {added:?}"));
+    }
+    Outcome::Written(written)
+}
+
+/// The path of every key of `value`, like `slots[0].required.x`.
+fn key_paths(value: &Value) -> BTreeSet<String> {
+    fn collect(out: &mut BTreeSet<String>, path: &str, value: &Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let path = match path.is_empty() {
+                        true => key.clone(),
+                        false => format!("{path}.{key}"),
+                    };
+                    collect(out, &path, value);
+                    out.insert(path);
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    collect(out, &format!("{path}[{i}]"), item);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut out = BTreeSet::new();
+    collect(&mut out, "", value);
+    out
+}
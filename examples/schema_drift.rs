@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+use research_assistant::{config::Config, drift::DriftReport, loader::Loader};
+use serde_json::{Map, Value};
+use std::env::args;
+
+/// Usage: `schema_drift [--json] [--all]`
+///
+/// Compares the keys of the raw content files with the data model. Without `--all` only keys that
+/// aren't modelled, modelled keys that never appear and keys with mismatching values are listed.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut json = false;
+    let mut all = false;
+    for arg in args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--all" => all = true,
+            _ => bail!("unknown argument {arg:?}"),
+        }
+    }
+
+    let config = Config::read_config()?.resolve()?;
+    let mut loader = Loader::new();
+    let mut files = Vec::new();
+    for file in loader.read_files::<Map<String, Value>>(&config) {
+        match file {
+            Ok((_, file)) => files.push(file),
+            Err(err) => eprintln!("Err {err}"),
+        }
+    }
+    let report = DriftReport::build(files);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    for (kind, drift) in &report.kinds {
+        println!("{kind} ({} entities)", drift.entities);
+        for (path, key) in &drift.keys {
+            let mut flags = Vec::new();
+            if !key.modelled {
+                flags.push("not modelled".to_owned());
+            }
+            let unexpected: Vec<_> = key.unexpected_types().map(|ty| ty.as_str()).collect();
+            if !unexpected.is_empty() {
+                flags.push(format!("unexpected {}", unexpected.join("/")));
+            }
+            for (message, count) in &key.mismatches {
+                flags.push(format!("{count}x {message}"));
+            }
+            if flags.is_empty() && !all {
+                continue;
+            }
+
+            let types: Vec<_> = key
+                .types
+                .iter()
+                .map(|(ty, count)| format!("{ty}: {count}"))
+                .collect();
+            let examples: Vec<_> = key.examples.iter().map(Value::to_string).collect();
+            print!("  {path} [{}]", types.join(", "));
+            if key.modelled {
+                let expected: Vec<_> = key.expected.iter().map(|e| e.as_str()).collect();
+                let nullable = if key.nullable { "?" } else { "" };
+                print!(" expected [{}{nullable}]", expected.join("/"));
+            }
+            match examples.is_empty() {
+                true => println!(),
+                false => println!(" e.g. {}", examples.join(", ")),
+            }
+            for flag in flags {
+                println!("    ! {flag}");
+            }
+        }
+        for path in &drift.unseen {
+            println!("  {path} never used");
+        }
+    }
+    Ok(())
+}
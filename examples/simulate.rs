@@ -0,0 +1,48 @@
+use anyhow::{Context, Result};
+use research_assistant::{
+    config::Config,
+    inherit::resolve_inheritance,
+    loader::Loader,
+    simulate::{simulate, Situation},
+};
+use std::env::args;
+
+/// Usage: `simulate <verb> [element]...`, an element can be given multiple times.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let mut args = args().skip(1);
+    let mut situation = Situation::new(args.next().context("expected a verb id")?);
+    for element in args {
+        *situation.slotted.entry(element).or_default() += 1;
+    }
+
+    let config = Config::read_config()?.resolve()?;
+    let mut compendium = Loader::new().load(&config)?;
+    resolve_inheritance(&mut compendium);
+
+    let simulation = simulate(&compendium, &situation);
+    for recipe in simulation.eligible {
+        println!("eligible {}", recipe.id);
+    }
+    for recipe in simulation.hints {
+        println!("hint {}", recipe.id);
+    }
+    for near_miss in simulation.near_misses {
+        println!("near miss {}", near_miss.recipe.id);
+        for failure in near_miss.failures {
+            println!("    {failure}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use research_assistant::{
+    config::Config,
+    inherit::resolve_inheritance,
+    loader::Loader,
+    validate::{dangling_references, unknown_fields},
+};
+
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let config = Config::read_config()?.resolve()?;
+    let mut compendium = Loader::new().load(&config)?;
+    // Checked before inheritance, so a reference a child inherits is only reported for the parent
+    for dangling in dangling_references(&compendium) {
+        println!("{dangling}");
+    }
+    for unknown in unknown_fields(&compendium) {
+        println!("{unknown}");
+    }
+    for err in resolve_inheritance(&mut compendium) {
+        println!("{err}");
+    }
+    Ok(())
+}
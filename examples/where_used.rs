@@ -0,0 +1,37 @@
+use anyhow::Result;
+use research_assistant::{config::Config, loader::Loader, usage::UsageIndex};
+use std::env::args;
+
+/// Usage: `where_used [id]...`
+///
+/// Without ids the whole index is dumped as JSON.
+pub fn main() -> Result<()> {
+    match run() {
+        Ok(()) => (),
+        Err(err) => {
+            eprintln!("Err {err}");
+            eprintln!("Err {err:#?}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let ids: Vec<String> = args().skip(1).collect();
+
+    let config = Config::read_config()?.resolve()?;
+    // Without resolving inheritance, so a usage belongs to the entity that actually writes it
+    let compendium = Loader::new().load(&config)?;
+    let index = UsageIndex::build(&compendium);
+    if ids.is_empty() {
+        println!("{}", serde_json::to_string_pretty(&index)?);
+    }
+    for id in &ids {
+        println!("{id}:");
+        for usage in index.get(id) {
+            println!("    {usage}");
+        }
+    }
+    Ok(())
+}
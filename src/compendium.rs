@@ -0,0 +1,171 @@
+use crate::{
+    data::{
+        Achievements, Cultures, Data, Decks, Dicta, Elements, Endings, Entity, EntityKind,
+        Legacies, Levers, Portals, Recipes, Settings, Verbs,
+    },
+    span::{Location, Span},
+    unknown::Extra,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    ops::{Bound, Deref, DerefMut},
+    path::PathBuf,
+};
+
+/// A content file, given as the source root it was found in and its path relative to that root.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct SourceFile {
+    pub root: PathBuf,
+    pub path: PathBuf,
+}
+
+impl SourceFile {
+    pub fn full_path(&self) -> PathBuf {
+        self.root.join(&self.path)
+    }
+}
+
+impl Display for SourceFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.full_path().display())
+    }
+}
+
+/// An entity together with the file it was loaded from.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sourced<T> {
+    pub source: SourceFile,
+    /// Where in [`Self::source`] the entity is, [`None`] if it wasn't read from text.
+    pub span: Option<Span>,
+    /// Keys of the entity that aren't part of the data model, see
+    /// [`crate::unknown::UnknownFields::Capture`].
+    #[serde(skip_serializing_if = "Extra::is_empty")]
+    pub extra: Extra,
+    pub value: T,
+}
+
+impl<T> Sourced<T> {
+    pub fn location(&self) -> Location<'_> {
+        Location(&self.source, self.span)
+    }
+}
+
+impl<T> Deref for Sourced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Sourced<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+/// Every entity of every source, indexed by `id`.
+///
+/// Entities are inserted in source order, so a later definition of an `id` replaces an earlier one.
+#[derive(Debug, Default, Serialize)]
+pub struct Compendium {
+    pub achievements: BTreeMap<String, Sourced<Achievements>>,
+    pub cultures: BTreeMap<String, Sourced<Cultures>>,
+    pub decks: BTreeMap<String, Sourced<Decks>>,
+    pub dicta: BTreeMap<String, Sourced<Dicta>>,
+    pub elements: BTreeMap<String, Sourced<Elements>>,
+    pub endings: BTreeMap<String, Sourced<Endings>>,
+    pub legacies: BTreeMap<String, Sourced<Legacies>>,
+    pub levers: BTreeMap<String, Sourced<Levers>>,
+    pub portals: BTreeMap<String, Sourced<Portals>>,
+    pub recipes: BTreeMap<String, Sourced<Recipes>>,
+    pub settings: BTreeMap<String, Sourced<Settings>>,
+    pub verbs: BTreeMap<String, Sourced<Verbs>>,
+}
+
+impl Compendium {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether an entity of `kind` matches `id`.
+    ///
+    /// Like the engine, an `id` ending in `*` matches every id starting with the rest of it.
+    pub fn contains(&self, kind: EntityKind, id: &str) -> bool {
+        match kind {
+            EntityKind::Achievements => contains(&self.achievements, id),
+            EntityKind::Cultures => contains(&self.cultures, id),
+            EntityKind::Decks => contains(&self.decks, id),
+            EntityKind::Dicta => contains(&self.dicta, id),
+            EntityKind::Elements => contains(&self.elements, id),
+            EntityKind::Endings => contains(&self.endings, id),
+            EntityKind::Legacies => contains(&self.legacies, id),
+            EntityKind::Levers => contains(&self.levers, id),
+            EntityKind::Portals => contains(&self.portals, id),
+            EntityKind::Recipes => contains(&self.recipes, id),
+            EntityKind::Settings => contains(&self.settings, id),
+            EntityKind::Verbs => contains(&self.verbs, id),
+        }
+    }
+
+    /// Add every entity of `data`, which was loaded from `source`.
+    pub fn insert_data(&mut self, source: &SourceFile, data: Data) {
+        let Data {
+            achievements,
+            cultures,
+            decks,
+            dicta,
+            elements,
+            endings,
+            legacies,
+            levers,
+            portals,
+            recipes,
+            settings,
+            verbs,
+        } = data;
+
+        insert_all(&mut self.achievements, source, achievements);
+        insert_all(&mut self.cultures, source, cultures);
+        insert_all(&mut self.decks, source, decks);
+        insert_all(&mut self.dicta, source, dicta);
+        insert_all(&mut self.elements, source, elements);
+        insert_all(&mut self.endings, source, endings);
+        insert_all(&mut self.legacies, source, legacies);
+        insert_all(&mut self.levers, source, levers);
+        insert_all(&mut self.portals, source, portals);
+        insert_all(&mut self.recipes, source, recipes);
+        insert_all(&mut self.settings, source, settings);
+        insert_all(&mut self.verbs, source, verbs);
+    }
+}
+
+fn insert_all<T: Entity>(
+    map: &mut BTreeMap<String, Sourced<T>>,
+    source: &SourceFile,
+    entities: Vec<T>,
+) {
+    for value in entities {
+        map.insert(
+            value.id().to_owned(),
+            Sourced {
+                source: source.clone(),
+                span: None,
+                extra: Extra::new(),
+                value,
+            },
+        );
+    }
+}
+
+fn contains<T>(map: &BTreeMap<String, T>, id: &str) -> bool {
+    match id.strip_suffix('*') {
+        Some(prefix) => map
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .next()
+            .is_some_and(|(id, _)| id.starts_with(prefix)),
+        None => map.contains_key(id),
+    }
+}
@@ -1,76 +1,159 @@
-use anyhow::{Context, Result};
+use crate::{
+    discover::{self, SearchPaths},
+    unknown::UnknownFields,
+};
+use anyhow::{bail, Context, Result};
 use encoding_rs::Encoding;
-use globset::{Glob, GlobMatcher};
+use globset::{Glob, GlobBuilder, GlobMatcher};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    cmp::Reverse,
+    collections::HashSet,
+    error::Error,
+    fmt::{self, Display},
     fs::read_to_string,
+    io,
     path::{Path, PathBuf},
 };
 use walkdir::{DirEntry, WalkDir};
 
 mod impl_serde;
+mod layers;
+mod pattern;
+
+pub use layers::{ConfigFile, ConfigLayer, ConfigLayers, ExplainedValue, Explanation, CONFIG_ENV};
+pub use pattern::PatternList;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     source: Option<Vec<SourceFileConfig>>,
+    /// What to do with keys the data model doesn't know, defaults to `"strict"`
+    unknown_fields: Option<UnknownFields>,
 }
 
 impl Config {
+    /// Read and merge every config layer that exists (see [`ConfigLayers`]).
     pub fn read_config() -> Result<Self> {
-        Self::read_config_from_path("config.toml")
+        Self::read_config_with(None)
+    }
+
+    /// Like [`Config::read_config`], with `explicit` as the [`ConfigLayer::Explicit`] layer.
+    pub fn read_config_with(explicit: Option<&Path>) -> Result<Self> {
+        Ok(ConfigLayers::discover(explicit)?.merge())
     }
 
+    /// Read the config file at `path`, with relative roots made relative to its directory.
     pub fn read_config_from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
         let config = read_to_string(path).with_context(|| path.display().to_string())?;
-        toml::from_str(&config).map_err(Into::into)
+        let mut config: Self = toml::from_str(&config)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for source in config.source.iter_mut().flatten() {
+            let root = &source.root;
+            // Leave roots that are only expanded or discovered when resolving
+            let special = root == Path::new("auto")
+                || root.starts_with("~")
+                || root.to_str().is_some_and(|root| root.starts_with('$'));
+            if root.is_relative() && !special {
+                source.root = dir.join(root);
+            }
+        }
+        Ok(config)
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SourceFileConfig {
+    /// The content folder, or `"auto"` for the core content, DLC and mods of the first install
+    /// [`crate::discover`] finds (with the other options applied to each of them)
     pub root: PathBuf,
     pub follow_links: Option<bool>,
     pub max_depth: Option<usize>,
     pub glob: Option<SourceGlob>,
+    /// Gitignore-style globs of the files to load out of the JSON files the default glob matches,
+    /// can't be combined with [`Self::glob`] (see [`PatternList`])
+    pub include: Option<Vec<String>>,
     pub format: Option<SourceFormat>,
-    pub files: Option<BTreeMap<PathBuf, SourceFormat>>,
-    pub exclude: Option<BTreeSet<PathBuf>>,
+    /// Formats of the files matching a glob, instead of [`Self::format`] (see [`ResolvedFileFormats`])
+    pub files: Option<FileFormats>,
+    /// Gitignore-style globs of the files and directories to skip (see [`PatternList`])
+    pub exclude: Option<Vec<String>>,
 }
 
-#[derive(Debug)]
+impl SourceFileConfig {
+    /// A source that sets every option to what it resolves to by default, with an empty root.
+    pub(crate) fn defaults() -> Self {
+        let ResolvedSourceFormat {
+            autodetect,
+            encoding,
+            allow_trailing_comma,
+            strict,
+        } = DEFAULT_FORMAT;
+        Self {
+            root: PathBuf::new(),
+            follow_links: Some(false),
+            max_depth: None,
+            glob: Some(SourceGlob::Glob(DEFAULT_GLOB.to_owned())),
+            include: None,
+            format: Some(SourceFormat {
+                autodetect: Some(autodetect),
+                encoding,
+                allow_trailing_comma: Some(allow_trailing_comma),
+                strict: Some(strict),
+            }),
+            files: Some(FileFormats(Vec::new())),
+            exclude: Some(Vec::new()),
+        }
+    }
+}
+
+/// The glob of a source that doesn't set one.
+const DEFAULT_GLOB: &str = "*/*.json";
+
+/// The format of a source that doesn't set one.
+const DEFAULT_FORMAT: ResolvedSourceFormat = ResolvedSourceFormat {
+    autodetect: true,
+    encoding: None,
+    allow_trailing_comma: true,
+    strict: false,
+};
+
+#[derive(Debug, Clone)]
 pub enum SourceGlob {
     Glob(String),
     Enable(bool),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Glob patterns with the format of the files they match, in the order they are declared in.
+#[derive(Debug, Clone)]
+pub struct FileFormats(pub Vec<(String, SourceFormat)>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceFormat {
     /// Whether to enable bom sniffing, defaults to `true`
     pub autodetect: Option<bool>,
     /// Which character encoding to use based on <https://encoding.spec.whatwg.org/#concept-encoding-get>
     pub encoding: Option<&'static Encoding>,
     pub allow_trailing_comma: Option<bool>,
+    /// Whether to reject files that use extensions to standard JSON, defaults to `false`
+    pub strict: Option<bool>,
 }
 
 impl SourceFormat {
     fn resolve(format: Option<SourceFormat>) -> ResolvedSourceFormat {
-        let default = ResolvedSourceFormat {
-            autodetect: true,
-            encoding: None,
-            allow_trailing_comma: true,
-        };
+        let default = DEFAULT_FORMAT;
         match format {
             None => default,
             Some(SourceFormat {
                 autodetect,
                 encoding,
                 allow_trailing_comma,
+                strict,
             }) => ResolvedSourceFormat {
                 autodetect: autodetect.unwrap_or(default.autodetect),
                 encoding: encoding.or(default.encoding),
                 allow_trailing_comma: allow_trailing_comma.unwrap_or(default.allow_trailing_comma),
+                strict: strict.unwrap_or(default.strict),
             },
         }
     }
@@ -78,11 +161,20 @@ impl SourceFormat {
 
 impl Config {
     pub fn resolve(self) -> Result<ResolvedConfig> {
-        match self {
-            Config {
-                source: Some(sources),
-            } => Ok(ResolvedConfig {
-                source: sources
+        self.resolve_in(SearchPaths::from_env().as_ref())
+    }
+
+    /// Like [`Config::resolve`], but searches installs for `root = "auto"` in `paths` instead of
+    /// the home directory of the current user.
+    pub fn resolve_in(self, paths: Option<&SearchPaths>) -> Result<ResolvedConfig> {
+        let Config {
+            source,
+            unknown_fields,
+        } = self;
+        let unknown_fields = unknown_fields.unwrap_or_default();
+        match source {
+            Some(sources) => Ok(ResolvedConfig {
+                source: expand_auto(sources, paths)?
                     .into_iter()
                     .map(
                         |SourceFileConfig {
@@ -90,10 +182,17 @@ impl Config {
                              follow_links,
                              max_depth,
                              glob,
+                             include,
                              format,
                              files,
                              exclude,
                          }| {
+                            if glob.is_some() && include.is_some() {
+                                bail!(
+                                    "{}: only one of glob and include can be set",
+                                    root.display()
+                                );
+                            }
                             Ok(ResolvedSourceFileConfig {
                                 root: shellexpand::path::full(&root)?
                                     .canonicalize()
@@ -107,33 +206,55 @@ impl Config {
                                     ),
                                     Some(SourceGlob::Enable(true)) | None => {
                                         ResolvedSourceGlob::Glob(
-                                            Glob::new("*/*.json")?.compile_matcher(),
+                                            Glob::new(DEFAULT_GLOB)?.compile_matcher(),
                                         )
                                     }
                                 },
+                                include: include.map(PatternList::new).transpose()?,
                                 format: SourceFormat::resolve(format),
-                                files: match files {
-                                    None => BTreeMap::new(),
-                                    Some(map) => map
-                                        .into_iter()
-                                        .map(|(k, v)| Ok((k, SourceFormat::resolve(Some(v)))))
-                                        .collect::<Result<BTreeMap<PathBuf, ResolvedSourceFormat>>>(
-                                        )?,
-                                },
-                                exclude: exclude.unwrap_or_default(),
+                                files: ResolvedFileFormats::new(
+                                    (files.map(|files| files.0).unwrap_or_default().into_iter())
+                                        .map(|(glob, format)| {
+                                            (glob, SourceFormat::resolve(Some(format)))
+                                        }),
+                                )?,
+                                exclude: PatternList::new(exclude.unwrap_or_default())?,
                             })
                         },
                     )
                     .collect::<Result<Vec<_>>>()?,
+                unknown_fields,
             }),
-            Config { source: None } => Ok(ResolvedConfig { source: Vec::new() }),
+            None => Ok(ResolvedConfig {
+                source: Vec::new(),
+                unknown_fields,
+            }),
+        }
+    }
+}
+
+/// Replace every source with `root = "auto"` by the sources of the install that was found.
+fn expand_auto(
+    sources: Vec<SourceFileConfig>,
+    paths: Option<&SearchPaths>,
+) -> Result<Vec<SourceFileConfig>> {
+    let mut expanded = Vec::with_capacity(sources.len());
+    for source in sources {
+        match source.root == Path::new("auto") {
+            true => {
+                let paths = paths.context("can't find installs without $HOME")?;
+                expanded.extend(discover::auto_sources(&source, paths)?);
+            }
+            false => expanded.push(source),
         }
     }
+    Ok(expanded)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResolvedConfig {
     pub source: Vec<ResolvedSourceFileConfig>,
+    pub unknown_fields: UnknownFields,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,9 +263,10 @@ pub struct ResolvedSourceFileConfig {
     pub follow_links: bool,
     pub max_depth: usize,
     pub glob: ResolvedSourceGlob,
+    pub include: Option<PatternList>,
     pub format: ResolvedSourceFormat,
-    pub files: BTreeMap<PathBuf, ResolvedSourceFormat>,
-    pub exclude: BTreeSet<PathBuf>,
+    pub files: ResolvedFileFormats,
+    pub exclude: PatternList,
 }
 
 pub fn strip_root<'a>(root: &Path, entry: &'a DirEntry) -> &'a Path {
@@ -155,18 +277,64 @@ pub fn strip_root<'a>(root: &Path, entry: &'a DirEntry) -> &'a Path {
 }
 
 impl ResolvedSourceFileConfig {
-    pub fn source_files(
-        &self,
-    ) -> impl Iterator<Item = walkdir::Result<(DirEntry, ResolvedSourceFormat)>> + '_ {
+    /// The format of the file at `path` (relative to [`Self::root`]).
+    pub fn format_of(&self, path: &Path) -> ResolvedSourceFormat {
+        match self.files.rule_of(path) {
+            Some(rule) => rule.format,
+            None => self.format,
+        }
+    }
+
+    /// Whether the file or directory at `path` (relative to [`Self::root`]) is skipped by
+    /// [`Self::include`] or [`Self::exclude`].
+    ///
+    /// Directories are only skipped when they are excluded, or negated in [`Self::include`], so
+    /// whole subtrees can be pruned without walking them. Like in gitignore, everything inside a
+    /// skipped directory is skipped as well, even if a later pattern matches it.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let mut parents = path.ancestors().skip(1);
+        if parents
+            .any(|parent| !parent.as_os_str().is_empty() && self.is_excluded_self(parent, true))
+        {
+            return true;
+        }
+        self.is_excluded_self(path, is_dir)
+    }
+
+    /// Like [`Self::is_excluded`], without looking at the parent directories.
+    fn is_excluded_self(&self, path: &Path, is_dir: bool) -> bool {
+        if self.exclude.matched(path, is_dir) == Some(true) {
+            return true;
+        }
+        match (&self.include, is_dir) {
+            (None, _) => false,
+            (Some(include), true) => include.matched(path, true) == Some(false),
+            (Some(include), false) => include.matched(path, false) != Some(true),
+        }
+    }
+
+    /// Every file of this source that matches [`Self::glob`] and isn't skipped by
+    /// [`Self::is_excluded`], with the format to read it with.
+    ///
+    /// With [`Self::follow_links`], a file that is reachable through more than one path (like
+    /// through a symlink and directly) is only yielded the first time. Files that other sources
+    /// reach as well are skipped by [`crate::loader::Loader`].
+    pub fn source_files(&self) -> impl Iterator<Item = Result<SourceEntry, SourceFileError>> + '_ {
         let root = &self.root;
         let glob = match &self.glob {
             ResolvedSourceGlob::Glob(glob) => Some(glob),
             ResolvedSourceGlob::Disabled => None,
         };
+        let mut seen = HashSet::new();
         WalkDir::new(&self.root)
+            .follow_links(self.follow_links)
             .max_depth(self.max_depth)
             .into_iter()
-            .filter_entry(|entry| !self.exclude.contains(strip_root(root, entry)))
+            // Excluded directories are pruned, so only the entry itself has to be checked
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !self.is_excluded_self(strip_root(root, entry), entry.file_type().is_dir())
+            })
             .filter_map(move |entry| match entry {
                 Ok(entry) => match entry.file_type().is_file() {
                     false => None,
@@ -180,21 +348,180 @@ impl ResolvedSourceFileConfig {
                             return None;
                         }
 
-                        let config = self.files.get(path).unwrap_or(&self.format);
+                        let format = self.format_of(path);
+                        let canonical = match entry.path().canonicalize() {
+                            Ok(canonical) => canonical,
+                            Err(error) => {
+                                return Some(Err(SourceFileError::Canonicalize {
+                                    path: entry.into_path(),
+                                    error,
+                                }))
+                            }
+                        };
+                        if !seen.insert(canonical.clone()) {
+                            return None;
+                        }
 
-                        Some(Ok((entry, *config)))
+                        Some(Ok(SourceEntry {
+                            entry,
+                            canonical,
+                            format,
+                        }))
                     }
                 },
-                Err(err) => Some(Err(err)),
+                Err(err) => Some(Err(SourceFileError::from(err))),
+            })
+    }
+}
+
+/// A file found by [`ResolvedSourceFileConfig::source_files`].
+#[derive(Debug, Clone)]
+pub struct SourceEntry {
+    /// The file at the path it was found at, which can go through symlinks.
+    pub entry: DirEntry,
+    /// The path of the file with every symlink resolved.
+    pub canonical: PathBuf,
+    pub format: ResolvedSourceFormat,
+}
+
+#[derive(Debug)]
+pub enum SourceFileError {
+    /// Following the symlink at `link` leads back to `ancestor`, one of its parent directories.
+    Loop { link: PathBuf, ancestor: PathBuf },
+    /// Resolving the symlinks in the path of a file failed.
+    Canonicalize { path: PathBuf, error: io::Error },
+    /// Walking the source directory failed.
+    Walk(walkdir::Error),
+}
+
+impl SourceFileError {
+    /// The path the error happened at.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            SourceFileError::Loop { link, .. } => Some(link),
+            SourceFileError::Canonicalize { path, .. } => Some(path),
+            SourceFileError::Walk(err) => err.path(),
+        }
+    }
+}
+
+impl From<walkdir::Error> for SourceFileError {
+    fn from(err: walkdir::Error) -> Self {
+        match (err.path(), err.loop_ancestor()) {
+            (Some(link), Some(ancestor)) => SourceFileError::Loop {
+                link: link.to_owned(),
+                ancestor: ancestor.to_owned(),
+            },
+            _ => SourceFileError::Walk(err),
+        }
+    }
+}
+
+impl Display for SourceFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceFileError::Loop { link, ancestor } => write!(
+                f,
+                "symlink loop: {} leads back to {}",
+                link.display(),
+                ancestor.display()
+            ),
+            SourceFileError::Canonicalize { path, error } => {
+                write!(f, "{}: {error}", path.display())
+            }
+            SourceFileError::Walk(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for SourceFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SourceFileError::Loop { .. } => None,
+            SourceFileError::Canonicalize { error, .. } => Some(error),
+            SourceFileError::Walk(err) => Some(err),
+        }
+    }
+}
+
+/// The rules of [`SourceFileConfig::files`], ordered by precedence.
+///
+/// Globs are relative to the root, and `*` doesn't match `/` but `**` does. When several globs
+/// match a file, the most specific one wins:
+///
+/// 1. A plain path (without any glob syntax) beats every glob.
+/// 2. Otherwise the glob with more literal characters wins, so `loc/de/*.json` beats `loc/*/*.json`.
+/// 3. Ties go to the glob declared first.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedFileFormats {
+    rules: Vec<FileRule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileRule {
+    pub glob: GlobMatcher,
+    pub format: ResolvedSourceFormat,
+}
+
+impl ResolvedFileFormats {
+    pub fn new<I: IntoIterator<Item = (String, ResolvedSourceFormat)>>(
+        rules: I,
+    ) -> Result<Self, globset::Error> {
+        let mut rules = rules
+            .into_iter()
+            .map(|(glob, format)| {
+                Ok(FileRule {
+                    glob: GlobBuilder::new(&glob)
+                        .literal_separator(true)
+                        .build()?
+                        .compile_matcher(),
+                    format,
+                })
             })
+            .collect::<Result<Vec<_>, globset::Error>>()?;
+        // Stable, so ties keep their declaration order
+        rules.sort_by_key(|rule| Reverse(specificity(rule.glob.glob().glob())));
+        Ok(Self { rules })
+    }
+
+    /// The rules, from the highest precedence to the lowest.
+    pub fn rules(&self) -> &[FileRule] {
+        &self.rules
+    }
+
+    /// The rule that picks the format of the file at `path` (relative to the root).
+    pub fn rule_of(&self, path: &Path) -> Option<&FileRule> {
+        self.rules.iter().find(|rule| rule.glob.is_match(path))
     }
 }
 
+/// Whether `glob` is a plain path, and how many of its characters match literally.
+fn specificity(glob: &str) -> (bool, usize) {
+    let (mut literal, mut depth, mut escaped) = (0, 0usize, false);
+    for char in glob.chars() {
+        match char {
+            _ if escaped => {
+                escaped = false;
+                literal += usize::from(depth == 0);
+            }
+            '\\' => escaped = true,
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            '*' | '?' => (),
+            _ => literal += usize::from(depth == 0),
+        }
+    }
+    let plain = !glob.contains(['*', '?', '[', ']', '{', '}']);
+    (plain, literal)
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ResolvedSourceFormat {
     pub autodetect: bool,
     pub encoding: Option<&'static Encoding>,
     pub allow_trailing_comma: bool,
+    /// Reject files that use any [`crate::strict::Extension`], instead of only the disabled ones.
+    pub strict: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -202,3 +529,173 @@ pub enum ResolvedSourceGlob {
     Glob(GlobMatcher),
     Disabled,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use std::fs;
+
+    fn source(options: &str) -> ResolvedSourceFileConfig {
+        let config: Config =
+            toml::from_str(&format!("[[source]]\nroot = \".\"\n{options}")).unwrap();
+        config.resolve().unwrap().source.remove(0)
+    }
+
+    fn source_with_include(include: &[&str]) -> ResolvedSourceFileConfig {
+        source(&format!("include = {include:?}"))
+    }
+
+    #[test]
+    fn core_minus_cultures_minus_tests() {
+        let source = source(
+            r#"include = ["core/"]
+exclude = ["core/cultures/", "*_test.json"]"#,
+        );
+        let excluded = |path: &str, is_dir| source.is_excluded(Path::new(path), is_dir);
+        assert!(!excluded("core", true));
+        assert!(!excluded("core/elements", true));
+        assert!(!excluded("core/elements/tools.json", false));
+        assert!(excluded("core/elements/tools_test.json", false));
+        assert!(excluded("core/cultures", true));
+        assert!(excluded("core/cultures/culture.json", false));
+        assert!(excluded("dlc/elements/tools.json", false));
+    }
+
+    #[test]
+    fn no_reinclude_inside_excluded_directory() {
+        let source = source(r#"exclude = ["cultures/", "!cultures/keep.json"]"#);
+        assert!(source.is_excluded(Path::new("core/cultures"), true));
+        assert!(source.is_excluded(Path::new("core/cultures/keep.json"), false));
+
+        let source = source_with_include(&["core/", "!core/cultures/", "core/cultures/keep.json"]);
+        assert!(source.is_excluded(Path::new("core/cultures"), true));
+        assert!(source.is_excluded(Path::new("core/cultures/keep.json"), false));
+    }
+
+    /// The glob of the rule picking the format of each path.
+    fn rules_of<'a>(files: &'a ResolvedFileFormats, paths: &[&str]) -> Vec<Option<&'a str>> {
+        (paths.iter())
+            .map(|path| Some(files.rule_of(Path::new(path))?.glob.glob().glob()))
+            .collect()
+    }
+
+    fn file_formats(globs: &[&str]) -> ResolvedFileFormats {
+        ResolvedFileFormats::new(globs.iter().map(|glob| (glob.to_string(), DEFAULT_FORMAT)))
+            .unwrap()
+    }
+
+    #[test]
+    fn file_format_precedence() {
+        let files = file_formats(&[
+            "loc/*/*.json",
+            "**/*.json",
+            "loc/de/*.json",
+            "loc/de/x.json",
+        ]);
+        assert_eq!(
+            rules_of(
+                &files,
+                &["loc/de/x.json", "loc/de/y.json", "loc/en/y.json", "y.json"]
+            ),
+            [
+                // A plain path beats every glob
+                Some("loc/de/x.json"),
+                // More literal characters win
+                Some("loc/de/*.json"),
+                Some("loc/*/*.json"),
+                Some("**/*.json"),
+            ]
+        );
+        assert_eq!(rules_of(&files, &["loc/de/x.txt"]), [None]);
+
+        // Ties go to the glob declared first
+        let paths = ["x/x.json"];
+        assert_eq!(
+            rules_of(&file_formats(&["x/*.json", "*/x.json"]), &paths),
+            [Some("x/*.json")]
+        );
+        assert_eq!(
+            rules_of(&file_formats(&["*/x.json", "x/*.json"]), &paths),
+            [Some("*/x.json")]
+        );
+    }
+
+    #[test]
+    fn file_formats_keep_toml_order() {
+        // Sorted by key, `*/x.json` would come first
+        let source = source(
+            r#"[source.files]
+"x/*.json" = { strict = true }
+"*/x.json" = {}"#,
+        );
+        let globs: Vec<_> = (source.files.rules().iter())
+            .map(|rule| rule.glob.glob().glob())
+            .collect();
+        assert_eq!(globs, ["x/*.json", "*/x.json"]);
+        assert!(source.format_of(Path::new("x/x.json")).strict);
+    }
+
+    #[test]
+    fn roots_relative_to_config_file() {
+        let dir = TempDir::new("config-roots");
+        let path = dir.join("project/config.toml");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            r#"[[source]]
+root = "core"
+[[source]]
+root = "auto"
+[[source]]
+root = "/core""#,
+        )
+        .unwrap();
+        let config = Config::read_config_from_path(&path).unwrap();
+        let roots: Vec<_> = (config.source.iter().flatten())
+            .map(|source| source.root.clone())
+            .collect();
+        assert_eq!(
+            roots,
+            [
+                dir.join("project/core"),
+                PathBuf::from("auto"),
+                PathBuf::from("/core"),
+            ]
+        );
+    }
+
+    #[test]
+    fn source_files() {
+        let dir = TempDir::new("config");
+        for file in [
+            "core/elements/tools.json",
+            "core/elements/tools_test.json",
+            "core/elements/notes.txt",
+            "core/cultures/culture.json",
+            "core/cultures/keep.json",
+            "dlc/elements/dlc.json",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+
+        let config: Config = toml::from_str(&format!(
+            r#"[[source]]
+root = {:?}
+include = ["core/"]
+exclude = ["cultures/", "!cultures/keep.json", "*_test.json"]"#,
+            &*dir
+        ))
+        .unwrap();
+        let config = config.resolve().unwrap();
+        let source = &config.source[0];
+        let mut files: Vec<_> = (source.source_files())
+            .map(|file| strip_root(&source.root, &file.unwrap().entry).to_owned())
+            .collect();
+        files.sort();
+        // Neither the text file, nor anything inside the excluded directory
+        assert_eq!(files, [Path::new("core/elements/tools.json")]);
+    }
+}
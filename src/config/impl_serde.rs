@@ -1,6 +1,11 @@
-use super::{ResolvedSourceGlob, SourceGlob};
+use super::{FileFormats, ResolvedFileFormats, ResolvedSourceGlob, SourceGlob};
 use globset::Glob;
-use serde::{de::Visitor, Deserialize, Serialize};
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Serialize,
+};
+use std::marker::PhantomData;
 
 impl Serialize for SourceGlob {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -107,3 +112,68 @@ impl<'de> Visitor<'de> for ResolvedSourceGlobVisitor {
         }
     }
 }
+
+impl Serialize for FileFormats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(glob, format)| (glob, format)))
+    }
+}
+
+impl<'de> Deserialize<'de> for FileFormats {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_map(OrderedMapVisitor(PhantomData))
+            .map(FileFormats)
+    }
+}
+
+impl Serialize for ResolvedFileFormats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.rules.len()))?;
+        for rule in &self.rules {
+            map.serialize_entry(rule.glob.glob().glob(), &rule.format)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ResolvedFileFormats {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let rules = deserializer.deserialize_map(OrderedMapVisitor(PhantomData))?;
+        ResolvedFileFormats::new(rules).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A map as its entries, in the order they appear in.
+struct OrderedMapVisitor<V>(PhantomData<V>);
+
+impl<'de, V: Deserialize<'de>> Visitor<'de> for OrderedMapVisitor<V> {
+    type Value = Vec<(String, V)>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a map of glob patterns")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
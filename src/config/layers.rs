@@ -0,0 +1,453 @@
+use super::{Config, SourceFileConfig};
+use crate::{discover::SearchPaths, unknown::UnknownFields};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashSet,
+    env,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+/// The environment variable with the path of a config file.
+pub const CONFIG_ENV: &str = "RESEARCH_ASSISTANT_CONFIG";
+
+/// Where a config file was found, from the highest precedence to the lowest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayer {
+    /// A path that was passed explicitly.
+    Explicit,
+    /// The path in [`CONFIG_ENV`].
+    Env,
+    /// `config.toml` in the current directory.
+    Local,
+    /// `$XDG_CONFIG_HOME/research-assistant/config.toml`
+    User,
+}
+
+impl ConfigLayer {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConfigLayer::Explicit => "explicit",
+            ConfigLayer::Env => "env",
+            ConfigLayer::Local => "local",
+            ConfigLayer::User => "user",
+        }
+    }
+}
+
+impl Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigFile {
+    pub layer: ConfigLayer,
+    pub path: PathBuf,
+    pub config: Config,
+}
+
+/// Every config file that was found, ordered by precedence (highest first).
+///
+/// They are merged into one [`Config`] like this:
+///
+/// - The sources of every layer are appended, from the lowest layer to the highest, so the
+///   content of a higher layer is loaded later.
+/// - Every other value is taken from the highest layer that sets it.
+///
+/// Relative source roots are relative to the directory of the file they are in.
+#[derive(Debug)]
+pub struct ConfigLayers {
+    pub files: Vec<ConfigFile>,
+}
+
+impl ConfigLayers {
+    /// Read every layer that exists, with `explicit` as the [`ConfigLayer::Explicit`] one.
+    ///
+    /// An explicit path or one in [`CONFIG_ENV`] has to exist, the other layers are skipped if they
+    /// don't. A file that is found by several layers is only read for the highest of them.
+    pub fn discover(explicit: Option<&Path>) -> Result<Self> {
+        let user = SearchPaths::from_env()
+            .map(|paths| paths.config_home.join("research-assistant/config.toml"));
+        let candidates = [
+            (ConfigLayer::Explicit, explicit.map(Path::to_owned), true),
+            (
+                ConfigLayer::Env,
+                env::var_os(CONFIG_ENV).map(PathBuf::from),
+                true,
+            ),
+            (
+                ConfigLayer::Local,
+                Some(PathBuf::from("config.toml")),
+                false,
+            ),
+            (ConfigLayer::User, user, false),
+        ];
+
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for (layer, path, required) in candidates {
+            let Some(path) = path.filter(|path| !path.as_os_str().is_empty()) else {
+                continue;
+            };
+            if !required && !path.is_file() {
+                continue;
+            }
+            if !seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())) {
+                continue;
+            }
+            files.push(ConfigFile::read(layer, path)?);
+        }
+        if files.is_empty() {
+            bail!("no config found, set {CONFIG_ENV} or create config.toml");
+        }
+        Ok(Self { files })
+    }
+
+    /// Merge every layer into one config.
+    pub fn merge(&self) -> Config {
+        let mut merged = Config {
+            source: None,
+            unknown_fields: None,
+        };
+        for ConfigFile { config, .. } in self.files.iter().rev() {
+            let Config {
+                source,
+                unknown_fields,
+            } = config.clone();
+            if let Some(source) = source {
+                merged.source.get_or_insert_with(Vec::new).extend(source);
+            }
+            merged.unknown_fields = unknown_fields.or(merged.unknown_fields);
+        }
+        merged
+    }
+
+    /// Every value of the merged config, with the layer it came from, or as a default if no layer
+    /// sets it.
+    pub fn explain(&self) -> Explanation<'_> {
+        let defaults = object(&SourceFileConfig::defaults());
+        let mut values = Vec::new();
+        let mut index = 0;
+        for file in self.files.iter().rev() {
+            for source in file.config.source.iter().flatten() {
+                for (field, value) in object(source) {
+                    let key = format!("source[{index}].{field}");
+                    values.push(match value {
+                        Value::Null => ExplainedValue::default(key, defaults[&field].clone()),
+                        value => ExplainedValue::set(key, value, file, Vec::new()),
+                    });
+                }
+                index += 1;
+            }
+        }
+
+        let mut set =
+            (self.files.iter()).filter_map(|file| Some((file.config.unknown_fields?, file)));
+        let key = "unknown_fields".to_owned();
+        values.push(match set.next() {
+            Some((unknown_fields, file)) => ExplainedValue::set(
+                key,
+                serde_json::to_value(unknown_fields).expect("enums always serialize"),
+                file,
+                set.map(|(_, file)| (file.layer, &*file.path)).collect(),
+            ),
+            None => ExplainedValue::default(
+                key,
+                serde_json::to_value(UnknownFields::default()).expect("enums always serialize"),
+            ),
+        });
+        Explanation { values }
+    }
+}
+
+/// The fields of a source, with `null` for the ones that aren't set.
+fn object(source: &SourceFileConfig) -> Map<String, Value> {
+    match serde_json::to_value(source) {
+        Ok(Value::Object(object)) => object,
+        _ => unreachable!("sources always serialize to a map"),
+    }
+}
+
+impl ConfigFile {
+    fn read(layer: ConfigLayer, path: PathBuf) -> Result<Self> {
+        let config = Config::read_config_from_path(&path)?;
+        Ok(Self {
+            layer,
+            path,
+            config,
+        })
+    }
+}
+
+/// The values of a merged config, in the order [`ConfigLayers::merge`] applies them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct Explanation<'a> {
+    pub values: Vec<ExplainedValue<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainedValue<'a> {
+    /// The key in the merged config, like `unknown_fields` or `source[2].root`.
+    pub key: String,
+    pub value: Value,
+    /// The layer that sets this value, [`None`] for a default.
+    pub layer: Option<ConfigLayer>,
+    pub path: Option<&'a Path>,
+    /// The lower layers that set this value as well.
+    pub overrides: Vec<(ConfigLayer, &'a Path)>,
+}
+
+impl<'a> ExplainedValue<'a> {
+    fn set(
+        key: String,
+        value: Value,
+        file: &'a ConfigFile,
+        overrides: Vec<(ConfigLayer, &'a Path)>,
+    ) -> Self {
+        Self {
+            key,
+            value,
+            layer: Some(file.layer),
+            path: Some(&file.path),
+            overrides,
+        }
+    }
+
+    fn default(key: String, value: Value) -> Self {
+        Self {
+            key,
+            value,
+            layer: None,
+            path: None,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl Display for ExplainedValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}  # ", self.key, self.value)?;
+        match (self.layer, self.path) {
+            (Some(layer), Some(path)) => write!(f, "{layer} ({})", path.display())?,
+            _ => f.write_str("default")?,
+        }
+        for (layer, path) in &self.overrides {
+            write!(f, ", overrides {layer} ({})", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Explanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for value in &self.values {
+            writeln!(f, "{value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use std::fs::{self, create_dir_all};
+
+    /// A temporary directory for the config files of a test.
+    fn dir(name: &str) -> TempDir {
+        TempDir::new(&format!("layers-{name}"))
+    }
+
+    /// Read the config `toml` as `layer`, from a file in `dir/folder`.
+    fn file(dir: &Path, folder: &str, layer: ConfigLayer, toml: &str) -> ConfigFile {
+        let path = dir.join(folder).join("config.toml");
+        create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, toml).unwrap();
+        ConfigFile::read(layer, path).unwrap()
+    }
+
+    fn roots(config: &Config) -> Vec<PathBuf> {
+        (config.source.iter().flatten())
+            .map(|source| source.root.clone())
+            .collect()
+    }
+
+    #[test]
+    fn merge_precedence() {
+        let dir = dir("merge");
+        let layers = ConfigLayers {
+            files: vec![
+                file(
+                    &dir,
+                    "explicit",
+                    ConfigLayer::Explicit,
+                    "[[source]]\nroot = \"core\"",
+                ),
+                file(
+                    &dir,
+                    "local",
+                    ConfigLayer::Local,
+                    "unknown_fields = \"capture\"",
+                ),
+                file(
+                    &dir,
+                    "user",
+                    ConfigLayer::User,
+                    "unknown_fields = \"ignore\"\n[[source]]\nroot = \"mods\"",
+                ),
+            ],
+        };
+        let merged = layers.merge();
+        // Sources of lower layers come first, so higher ones are loaded later
+        assert_eq!(
+            roots(&merged),
+            [dir.join("user/mods"), dir.join("explicit/core")]
+        );
+        assert_eq!(merged.unknown_fields, Some(UnknownFields::Capture));
+
+        let layers = ConfigLayers {
+            files: vec![file(&dir, "env", ConfigLayer::Env, "")],
+        };
+        let merged = layers.merge();
+        assert!(merged.source.is_none());
+        assert_eq!(merged.unknown_fields, None);
+    }
+
+    #[test]
+    fn relative_roots() {
+        let dir = dir("roots");
+        let config = file(
+            &dir,
+            "project",
+            ConfigLayer::Local,
+            r#"
+            [[source]]
+            root = "core"
+            [[source]]
+            root = "../shared/core"
+            [[source]]
+            root = "/games/core"
+            "#,
+        );
+        assert_eq!(
+            roots(&config.config),
+            [
+                dir.join("project/core"),
+                dir.join("project/../shared/core"),
+                PathBuf::from("/games/core"),
+            ]
+        );
+    }
+
+    #[test]
+    fn special_roots() {
+        let dir = dir("special");
+        let config = file(
+            &dir,
+            "project",
+            ConfigLayer::User,
+            r#"
+            [[source]]
+            root = "auto"
+            [[source]]
+            root = "~/Games/book-of-hours"
+            [[source]]
+            root = "$GAMES/book-of-hours"
+            [[source]]
+            root = "autosave"
+            "#,
+        );
+        assert_eq!(
+            roots(&config.config),
+            [
+                PathBuf::from("auto"),
+                PathBuf::from("~/Games/book-of-hours"),
+                PathBuf::from("$GAMES/book-of-hours"),
+                dir.join("project/autosave"),
+            ]
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let dir = dir("explain");
+        let layers = ConfigLayers {
+            files: vec![
+                file(
+                    &dir,
+                    "local",
+                    ConfigLayer::Local,
+                    r#"unknown_fields = "capture"
+[[source]]
+root = "/core"
+exclude = ["cultures"]"#,
+                ),
+                file(
+                    &dir,
+                    "user",
+                    ConfigLayer::User,
+                    "unknown_fields = \"ignore\"",
+                ),
+            ],
+        };
+        let explanation = layers.explain();
+        let keys: Vec<_> = (explanation.values.iter())
+            .map(|value| value.key.as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "source[0].root",
+                "source[0].follow_links",
+                "source[0].max_depth",
+                "source[0].glob",
+                "source[0].include",
+                "source[0].format",
+                "source[0].files",
+                "source[0].exclude",
+                "unknown_fields",
+            ]
+        );
+        let local = dir.join("local/config.toml");
+        let user = dir.join("user/config.toml");
+        let lines: Vec<_> = (explanation.values.iter())
+            .map(|value| value.to_string())
+            .collect();
+        assert_eq!(
+            lines[0],
+            format!("source[0].root = \"/core\"  # local ({})", local.display())
+        );
+        assert_eq!(lines[1], "source[0].follow_links = false  # default");
+        assert_eq!(lines[3], "source[0].glob = \"*/*.json\"  # default");
+        assert_eq!(
+            lines[7],
+            format!(
+                "source[0].exclude = [\"cultures\"]  # local ({})",
+                local.display()
+            )
+        );
+        assert_eq!(
+            lines[8],
+            format!(
+                "unknown_fields = \"capture\"  # local ({}), overrides user ({})",
+                local.display(),
+                user.display()
+            )
+        );
+
+        let layers = ConfigLayers {
+            files: vec![file(&dir, "env", ConfigLayer::Env, "")],
+        };
+        let explanation = layers.explain();
+        assert_eq!(
+            explanation.to_string(),
+            "unknown_fields = \"strict\"  # default\n"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use globset::{Error, GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A list of globs with the semantics of a `.gitignore` file.
+///
+/// - A pattern without a `/` (like `*_test.json`) matches a file or directory with that name at
+///   any depth. Every other pattern is relative to the root, like `core/cultures` or `/recipes`.
+/// - `*` doesn't match `/`, but `**` does.
+/// - A pattern ending in `/` only matches directories.
+/// - A pattern starting with `!` negates an earlier match.
+/// - The last pattern that matches a path decides whether it is matched. A path no pattern matches
+///   is matched like its nearest parent directory that a pattern matches.
+/// - Like in gitignore, a file can't be re-included inside an excluded directory, since the
+///   directory is skipped as a whole: with `exclude = ["cultures/", "!cultures/keep.json"]`,
+///   `cultures/keep.json` is skipped too (see [`super::ResolvedSourceFileConfig::is_excluded`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct PatternList {
+    patterns: Vec<Pattern>,
+    set: GlobSet,
+}
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    source: String,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl PatternList {
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(patterns: I) -> Result<Self, Error> {
+        let mut set = GlobSetBuilder::new();
+        let patterns = patterns
+            .into_iter()
+            .map(|source| {
+                let source = source.into();
+                let (negated, pattern) = match source.strip_prefix('!') {
+                    Some(pattern) => (true, pattern),
+                    None => (false, &*source),
+                };
+                let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                    Some(pattern) => (true, pattern),
+                    None => (false, pattern),
+                };
+                let glob = match pattern.strip_prefix('/') {
+                    Some(pattern) => pattern.to_owned(),
+                    None if pattern.contains('/') => pattern.to_owned(),
+                    None => format!("**/{pattern}"),
+                };
+                set.add(GlobBuilder::new(&glob).literal_separator(true).build()?);
+                Ok(Pattern {
+                    source,
+                    negated,
+                    dir_only,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            patterns,
+            set: set.build()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether the last pattern matching `path` (relative to the root) is a positive one.
+    ///
+    /// If no pattern matches `path`, its nearest parent directory that one matches decides, and
+    /// if there is none either, this is [`None`]. Whether a parent directory is skipped as a whole
+    /// is up to the caller.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        path.ancestors()
+            .take_while(|path| !path.as_os_str().is_empty())
+            .enumerate()
+            .find_map(|(i, path)| self.matched_self(path, i > 0 || is_dir))
+    }
+
+    fn matched_self(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        self.set
+            .matches(path)
+            .into_iter()
+            .filter(|&i| is_dir || !self.patterns[i].dir_only)
+            .max()
+            .map(|i| !self.patterns[i].negated)
+    }
+}
+
+impl TryFrom<Vec<String>> for PatternList {
+    type Error = Error;
+
+    fn try_from(patterns: Vec<String>) -> Result<Self, Self::Error> {
+        Self::new(patterns)
+    }
+}
+
+impl From<PatternList> for Vec<String> {
+    fn from(list: PatternList) -> Self {
+        list.patterns
+            .into_iter()
+            .map(|pattern| pattern.source)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(patterns: &[&str], path: &str, is_dir: bool) -> Option<bool> {
+        PatternList::new(patterns.iter().copied())
+            .unwrap()
+            .matched(Path::new(path), is_dir)
+    }
+
+    #[test]
+    fn anchoring() {
+        let patterns = ["*_test.json", "core/cultures", "/recipes"];
+        assert_eq!(matched(&patterns, "a_test.json", false), Some(true));
+        assert_eq!(
+            matched(&patterns, "core/elements/a_test.json", false),
+            Some(true)
+        );
+        assert_eq!(matched(&patterns, "core/cultures", true), Some(true));
+        assert_eq!(matched(&patterns, "dlc/core/cultures", true), None);
+        assert_eq!(matched(&patterns, "recipes/a.json", false), Some(true));
+        assert_eq!(matched(&patterns, "core/recipes/a.json", false), None);
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(matched(&["core/*.json"], "core/a.json", false), Some(true));
+        assert_eq!(
+            matched(&["core/*.json"], "core/elements/a.json", false),
+            None
+        );
+        assert_eq!(
+            matched(&["core/**/*.json"], "core/elements/a.json", false),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn directories_only() {
+        assert_eq!(matched(&["cultures/"], "core/cultures", true), Some(true));
+        assert_eq!(matched(&["cultures/"], "core/cultures", false), None);
+        assert_eq!(
+            matched(&["cultures/"], "core/cultures/a.json", false),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn last_match_wins() {
+        let patterns = ["*.json", "!*_test.json", "keep_test.json"];
+        assert_eq!(matched(&patterns, "a.json", false), Some(true));
+        assert_eq!(matched(&patterns, "a_test.json", false), Some(false));
+        assert_eq!(matched(&patterns, "keep_test.json", false), Some(true));
+    }
+
+    #[test]
+    fn nearest_parent() {
+        let patterns = ["core/", "!core/cultures/"];
+        assert_eq!(
+            matched(&patterns, "core/elements/a.json", false),
+            Some(true)
+        );
+        assert_eq!(
+            matched(&patterns, "core/cultures/a.json", false),
+            Some(false)
+        );
+        assert_eq!(matched(&patterns, "dlc/a.json", false), None);
+    }
+}
@@ -1,3 +1,4 @@
+use super::{MorphEffect, XTriggerLevel, XTriggerTarget};
 use serde::{
     de::{
         value::{MapAccessDeserializer, SeqAccessDeserializer},
@@ -57,6 +58,24 @@ impl<'a> Visitor<'a> for ParseBoolVisitor {
     }
 }
 
+pub(super) fn parse_opt_bool<'de, D>(de: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::deserialize(de)?.map(|ParseBool(bool)| bool))
+}
+
+struct ParseBool(bool);
+
+impl<'de> Deserialize<'de> for ParseBool {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse_bool(deserializer).map(ParseBool)
+    }
+}
+
 pub(super) fn recipes_aspects<'de, D>(de: D) -> Result<Option<BTreeMap<String, i32>>, D::Error>
 where
     D: Deserializer<'de>,
@@ -247,7 +266,7 @@ impl<'de> Visitor<'de> for ParseF32Visitor {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StringOrI32 {
     Str(String),
     I32(i32),
@@ -319,7 +338,7 @@ impl<'de> Visitor<'de> for StringOrI32Visitor {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StringMapOrArray<T> {
     Str(String),
     Map(T),
@@ -398,7 +417,7 @@ where
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StringOrStruct<T> {
     Str(String),
     Struct(T),
@@ -458,7 +477,7 @@ where
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StringOrStringArray {
     Str(String),
     Arr(Vec<String>),
@@ -517,3 +536,137 @@ impl<'de> Visitor<'de> for StringOrStringArrayVisitor {
         )?))
     }
 }
+
+impl MorphEffect {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MorphEffect::Transform => "transform",
+            MorphEffect::Spawn => "spawn",
+            MorphEffect::Mutate => "mutate",
+            MorphEffect::SetMutation => "setmutation",
+        }
+    }
+}
+
+impl Serialize for MorphEffect {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for MorphEffect {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(MorphEffectVisitor)
+    }
+}
+
+struct MorphEffectVisitor;
+
+impl Visitor<'_> for MorphEffectVisitor {
+    type Value = MorphEffect;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("\"transform\", \"spawn\", \"mutate\" or \"setmutation\"")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        // The engine matches these case insensitively
+        match v.to_ascii_lowercase().as_str() {
+            "transform" => Ok(MorphEffect::Transform),
+            "spawn" => Ok(MorphEffect::Spawn),
+            "mutate" => Ok(MorphEffect::Mutate),
+            "setmutation" => Ok(MorphEffect::SetMutation),
+            _ => Err(E::invalid_value(serde::de::Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+impl Serialize for XTriggerTarget {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            XTriggerTarget::None => serializer.serialize_str(""),
+            XTriggerTarget::Catalyst => serializer.serialize_str("^"),
+            XTriggerTarget::Element(id) => serializer.serialize_str(id),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for XTriggerTarget {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match String::deserialize(deserializer)?.as_str() {
+            "" => XTriggerTarget::None,
+            "^" => XTriggerTarget::Catalyst,
+            id => XTriggerTarget::Element(id.to_owned()),
+        })
+    }
+}
+
+impl Serialize for XTriggerLevel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            XTriggerLevel::Fixed(level) => serializer.serialize_i32(*level),
+            XTriggerLevel::Catalyst => serializer.serialize_str("^"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for XTriggerLevel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(XTriggerLevelVisitor)
+    }
+}
+
+struct XTriggerLevelVisitor;
+
+impl Visitor<'_> for XTriggerLevelVisitor {
+    type Value = XTriggerLevel;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an i32 or \"^\"")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(XTriggerLevel::Fixed(v.try_into().map_err(E::custom)?))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(XTriggerLevel::Fixed(v.try_into().map_err(E::custom)?))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match v {
+            "^" => Ok(XTriggerLevel::Catalyst),
+            v => v.parse().map(XTriggerLevel::Fixed).map_err(E::custom),
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Draw odds of decks.
+//!
+//! A deck is shuffled once and drawn from without replacement. Once it is exhausted, it is
+//! either reshuffled (`resetonexhaustion`) or every further draw yields the `defaultcard`.
+
+use crate::{
+    compendium::Compendium,
+    data::{Decks, Recipes, RecipesInternalDeck},
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Deck {
+    /// The id of the deck, [`None`] for the internal deck of a recipe.
+    pub id: Option<String>,
+    /// The cards of `spec` and how many copies of each.
+    pub cards: BTreeMap<String, u32>,
+    pub default_card: Option<String>,
+    pub reset_on_exhaustion: bool,
+}
+
+impl From<&Decks> for Deck {
+    fn from(deck: &Decks) -> Self {
+        Self {
+            id: Some(deck.id.clone()),
+            cards: expand_spec(&deck.spec),
+            default_card: deck.default_card.clone(),
+            reset_on_exhaustion: deck.reset_on_exhaustion,
+        }
+    }
+}
+
+impl From<&RecipesInternalDeck> for Deck {
+    fn from(deck: &RecipesInternalDeck) -> Self {
+        Self {
+            id: None,
+            cards: expand_spec(&deck.spec),
+            default_card: deck.default_card.clone(),
+            reset_on_exhaustion: deck.reset_on_exhaustion,
+        }
+    }
+}
+
+fn expand_spec(spec: &[String]) -> BTreeMap<String, u32> {
+    let mut cards = BTreeMap::new();
+    for card in spec {
+        *cards.entry(card.clone()).or_default() += 1;
+    }
+    cards
+}
+
+/// The odds of drawing a card.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CardOdds {
+    pub at_least_once: f64,
+    pub expected: f64,
+    /// `distribution[n]` is the probability of drawing the card exactly `n` times.
+    pub distribution: Vec<f64>,
+}
+
+impl Deck {
+    pub fn size(&self) -> u32 {
+        self.cards.values().sum()
+    }
+
+    /// The exact odds of every card (including the default card) for `draws` draws from a freshly
+    /// shuffled deck.
+    pub fn odds(&self, draws: u32) -> BTreeMap<String, CardOdds> {
+        let size = self.size();
+        // Every card is drawn `base * copies` times for sure, the other `random` draws come from a
+        // freshly shuffled deck
+        let (base, random, extra_default) = match (size, self.reset_on_exhaustion) {
+            (0, _) => (0, 0, draws),
+            (size, true) => (draws / size, draws % size, 0),
+            (size, false) if draws <= size => (0, draws, 0),
+            (size, false) => (1, 0, draws - size),
+        };
+
+        let mut odds = BTreeMap::new();
+        for (card, &copies) in &self.cards {
+            let extra = match self.default_card.as_ref() == Some(card) {
+                true => extra_default,
+                false => 0,
+            };
+            odds.insert(
+                card.clone(),
+                card_odds(size, copies, random, base * copies + extra),
+            );
+        }
+        if let Some(default) = &self.default_card {
+            if !self.cards.contains_key(default) {
+                odds.insert(default.clone(), card_odds(size, 0, 0, extra_default));
+            }
+        }
+        odds
+    }
+
+    /// Estimate the odds of every card for `draws` draws by simulating `trials` shuffles.
+    ///
+    /// The same `seed` always gives the same result.
+    pub fn monte_carlo(&self, draws: u32, trials: u32, seed: u64) -> BTreeMap<String, CardOdds> {
+        let mut rng = SplitMix64(seed);
+        let spec: Vec<&str> = self
+            .cards
+            .iter()
+            .flat_map(|(card, &copies)| (0..copies).map(move |_| card.as_str()))
+            .collect();
+
+        let mut counts: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+        let mut deck = Vec::with_capacity(spec.len());
+        let mut drawn: BTreeMap<&str, u32> = BTreeMap::new();
+        for _ in 0..trials {
+            deck.clear();
+            drawn.clear();
+            for _ in 0..draws {
+                if deck.is_empty() && (self.reset_on_exhaustion || drawn.is_empty()) {
+                    deck.extend_from_slice(&spec);
+                    rng.shuffle(&mut deck);
+                }
+                match deck.pop().or(self.default_card.as_deref()) {
+                    Some(card) => *drawn.entry(card).or_default() += 1,
+                    None => break,
+                }
+            }
+            for (&card, &count) in &drawn {
+                let histogram = counts.entry(card).or_default();
+                if histogram.len() <= count as usize {
+                    histogram.resize(count as usize + 1, 0);
+                }
+                histogram[count as usize] += 1;
+            }
+        }
+
+        self.cards
+            .keys()
+            .map(String::as_str)
+            .chain(self.default_card.as_deref())
+            .map(|card| {
+                let mut histogram = counts.get(card).cloned().unwrap_or_default();
+                if histogram.is_empty() {
+                    histogram.push(0);
+                }
+                histogram[0] = trials - histogram[1..].iter().sum::<u32>();
+
+                let distribution: Vec<f64> = histogram
+                    .iter()
+                    .map(|&n| n as f64 / trials.max(1) as f64)
+                    .collect();
+                let odds = CardOdds {
+                    at_least_once: 1.0 - distribution[0],
+                    expected: distribution
+                        .iter()
+                        .enumerate()
+                        .map(|(n, p)| n as f64 * p)
+                        .sum(),
+                    distribution,
+                };
+                (card.to_owned(), odds)
+            })
+            .collect()
+    }
+}
+
+/// A card with `copies` out of `size` cards, drawn `random` times plus `base` times for sure.
+fn card_odds(size: u32, copies: u32, random: u32, base: u32) -> CardOdds {
+    let max = copies.min(random);
+    let mut distribution = vec![0.0; (base + max) as usize + 1];
+    for x in 0..=max {
+        distribution[(base + x) as usize] = hypergeometric(size, copies, random, x);
+    }
+    let expected = match size {
+        0 => base as f64,
+        size => base as f64 + random as f64 * copies as f64 / size as f64,
+    };
+    CardOdds {
+        at_least_once: 1.0 - distribution[0],
+        expected,
+        distribution,
+    }
+}
+
+/// The probability of drawing exactly `x` of `copies` cards in `draws` draws out of `size`.
+fn hypergeometric(size: u32, copies: u32, draws: u32, x: u32) -> f64 {
+    if x > copies || x > draws || draws - x > size - copies {
+        return 0.0;
+    }
+    (ln_choose(copies, x) + ln_choose(size - copies, draws - x) - ln_choose(size, draws)).exp()
+}
+
+fn ln_choose(n: u32, k: u32) -> f64 {
+    let k = k.min(n - k);
+    (1..=k).map(|i| ((n - k + i) as f64 / i as f64).ln()).sum()
+}
+
+/// The decks a recipe draws from, see [`recipe_draws`].
+#[derive(Debug, Clone, Serialize)]
+pub struct RecipeDraws<'a> {
+    /// Every deck and how many times it is drawn from, including the internal deck.
+    pub decks: Vec<(Deck, u32)>,
+    /// The ids in `deckeffects` that aren't a deck.
+    pub missing: Vec<&'a str>,
+}
+
+/// Every deck a recipe draws from and how many times, including its internal deck.
+pub fn recipe_draws<'a>(compendium: &Compendium, recipe: &'a Recipes) -> RecipeDraws<'a> {
+    let mut draws = RecipeDraws {
+        decks: Vec::new(),
+        missing: Vec::new(),
+    };
+    for (id, &count) in recipe.deck_effects.iter().flatten() {
+        match compendium.decks.get(id) {
+            Some(deck) => draws.decks.push((Deck::from(&**deck), count)),
+            None => draws.missing.push(id),
+        }
+    }
+    if let Some(deck) = &recipe.internal_deck {
+        draws.decks.push((Deck::from(deck), deck.draws));
+    }
+    draws
+}
+
+/// The SplitMix64 generator, which is good enough for shuffling.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compendium::SourceFile, data::Data};
+    use serde_json::json;
+
+    fn deck(spec: &[(&str, u32)], default_card: Option<&str>, reset_on_exhaustion: bool) -> Deck {
+        Deck {
+            id: None,
+            cards: (spec.iter())
+                .map(|&(card, copies)| (card.to_owned(), copies))
+                .collect(),
+            default_card: default_card.map(str::to_owned),
+            reset_on_exhaustion,
+        }
+    }
+
+    fn assert_close(exact: &BTreeMap<String, CardOdds>, estimate: &BTreeMap<String, CardOdds>) {
+        assert_eq!(
+            exact.keys().collect::<Vec<_>>(),
+            estimate.keys().collect::<Vec<_>>()
+        );
+        for (card, exact) in exact {
+            let estimate = &estimate[card];
+            let close = |a: f64, b: f64| (a - b).abs() < 0.01;
+            assert!(close(exact.at_least_once, estimate.at_least_once), "{card}");
+            assert!(close(exact.expected, estimate.expected), "{card}");
+            let len = exact.distribution.len().max(estimate.distribution.len());
+            for n in 0..len {
+                let p = |odds: &CardOdds| odds.distribution.get(n).copied().unwrap_or(0.0);
+                assert!(close(p(exact), p(estimate)), "{card}[{n}]");
+            }
+        }
+    }
+
+    #[test]
+    fn exact_hypergeometric() {
+        let odds = deck(&[("a", 2), ("b", 3)], None, false).odds(2);
+        let a = &odds["a"];
+        let expected = [0.3, 0.6, 0.1];
+        assert_eq!(a.distribution.len(), expected.len());
+        for (p, expected) in a.distribution.iter().zip(expected) {
+            assert!((p - expected).abs() < 1e-12, "{p} != {expected}");
+        }
+        assert!((a.at_least_once - 0.7).abs() < 1e-12);
+        assert!((a.expected - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn exact_matches_monte_carlo() {
+        let cases = [
+            (deck(&[("a", 2), ("b", 3)], None, false), 3),
+            (deck(&[("a", 1), ("b", 2)], Some("c"), false), 5),
+            // The default card isn't drawn, but still has odds
+            (deck(&[("a", 1), ("b", 2)], Some("c"), false), 2),
+            (deck(&[("a", 1), ("b", 2)], Some("b"), false), 4),
+            (deck(&[("a", 2), ("b", 1)], None, true), 7),
+        ];
+        for (deck, draws) in cases {
+            assert_close(&deck.odds(draws), &deck.monte_carlo(draws, 100_000, 42));
+        }
+    }
+
+    #[test]
+    fn monte_carlo_is_deterministic() {
+        let deck = deck(&[("a", 2), ("b", 3)], None, false);
+        assert_eq!(deck.monte_carlo(2, 1000, 7), deck.monte_carlo(2, 1000, 7));
+    }
+
+    #[test]
+    fn recipe_draws_report_missing_decks() {
+        let mut compendium = Compendium::new();
+        let data: Data = serde_json::from_value(json!({
+            "decks": [{ "id": "omens", "spec": ["a", "b"] }],
+        }))
+        .unwrap();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "test.json".into(),
+        };
+        compendium.insert_data(&source, data);
+        let recipe: Recipes = serde_json::from_value(json!({
+            "id": "divine",
+            "deckeffects": { "omens": 2, "gone": 1 },
+            "internaldeck": { "spec": ["c"], "draws": 3, "resetonexhaustion": false },
+        }))
+        .unwrap();
+
+        let draws = recipe_draws(&compendium, &recipe);
+        let decks: Vec<_> = (draws.decks.iter())
+            .map(|(deck, draws)| (deck.id.as_deref(), *draws))
+            .collect();
+        assert_eq!(decks, [(Some("omens"), 2), (None, 3)]);
+        assert_eq!(draws.missing, ["gone"]);
+    }
+}
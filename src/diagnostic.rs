@@ -0,0 +1,521 @@
+//! Reports of content files that failed to load, for the terminal (see [`Display`]) or as JSON.
+//!
+//! ```text
+//! error: invalid type: string "x", expected u32
+//!   --> /game/content/core/elements/tools.json:12:19
+//!    = entity: elements[3] "hammer"
+//!    = path: elements[3].slots[0].required.x
+//!    = expected: u32
+//!    = found: string "x"
+//!    = encoding: UTF-8
+//!    |
+//! 11 |     "slots": [{
+//! 12 |       "required": { "x": "x" }
+//!    |                          ^^^
+//! 13 |     }]
+//! ```
+
+use crate::{
+    config::{ResolvedConfig, ResolvedSourceFormat},
+    data::{
+        Achievements, Cultures, Decks, Dicta, Elements, Endings, EntityKind, Legacies, Levers,
+        Portals, Recipes, Settings, Verbs,
+    },
+    loader::{LoadError, LoadErrorKind},
+    merge::EntityError,
+    reader::Reader,
+    span::{entity_spans, format_path, path_at, LineIndex, PathSegment, Span},
+    strict::NonStandardJson,
+};
+use encoding_rs::Encoding;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub file: PathBuf,
+    /// The encoding the file was decoded with.
+    pub encoding: Option<&'static str>,
+    /// The failing part of the file.
+    pub span: Option<Span>,
+    /// The path of the failing value, like `elements[3].slots[0].required`.
+    pub path: Option<String>,
+    pub entity: Option<EntityRef>,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+    /// The lines around [`Self::span`].
+    pub snippet: Vec<SnippetLine>,
+}
+
+/// The top-level entity a [`Diagnostic`] is in.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityRef {
+    pub kind: EntityKind,
+    /// The index of the entity in the file, if it is known.
+    pub index: Option<usize>,
+    /// The `id` of the entity, if it is readable.
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnippetLine {
+    pub line: usize,
+    /// The byte offset the line starts at.
+    pub start: usize,
+    pub text: String,
+}
+
+impl Diagnostic {
+    /// A diagnostic without any location inside the file.
+    pub fn new(file: PathBuf, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            file,
+            encoding: None,
+            span: None,
+            path: None,
+            entity: None,
+            expected: None,
+            found: None,
+            snippet: Vec::new(),
+        }
+    }
+
+    /// Describe `err`, which happened while deserializing `text` (read from `file`).
+    pub fn from_json_error(
+        file: PathBuf,
+        text: &str,
+        format: ResolvedSourceFormat,
+        err: &serde_json::Error,
+    ) -> Self {
+        let message = message_of(err);
+        let (expected, found) = expected_found(&message);
+        let mut diagnostic = Self {
+            expected,
+            found,
+            ..Self::new(file, message)
+        };
+        if err.line() != 0 {
+            let lines = LineIndex::new(text);
+            let offset = lines.offset(err.line(), err.column().saturating_sub(1));
+            diagnostic.locate(text, format, &lines, offset);
+        }
+        diagnostic
+    }
+
+    /// Describe `err`, which happened while deserializing an entity of `text` (read from `file`).
+    ///
+    /// The entity was deserialized from its merged value, which has no positions. So the text of
+    /// the entity is deserialized again, and if that fails the same way, its error points at the
+    /// failing value. Otherwise (like for an entity that only fails once it is merged) the
+    /// diagnostic points at the first line of the entity.
+    pub fn from_entity_error(
+        file: PathBuf,
+        text: &str,
+        format: ResolvedSourceFormat,
+        err: &EntityError,
+    ) -> Self {
+        let mut diagnostic = Self::from_json_error(file, "", format, &err.error);
+        let mut index = None;
+        if let Some(span) = err
+            .span
+            .filter(|span| text.get(span.start..span.end).is_some())
+        {
+            let lines = LineIndex::new(text);
+            let entity = &text[span.start..span.end];
+            let reparsed = deserialize_entity(err.kind, entity, format).filter(|reparsed| {
+                reparsed.line() != 0 && message_of(reparsed) == diagnostic.message
+            });
+            match reparsed {
+                Some(reparsed) => {
+                    let offset = LineIndex::new(entity)
+                        .offset(reparsed.line(), reparsed.column().saturating_sub(1));
+                    diagnostic.locate(text, format, &lines, span.start + offset);
+                    index = diagnostic.entity.as_ref().and_then(|entity| entity.index);
+                }
+                None => {
+                    let end = entity.find('\n').map_or(span.end, |end| span.start + end);
+                    let span = lines.span(span.start, end);
+                    diagnostic.snippet = snippet(text, &span);
+                    diagnostic.span = Some(span);
+                }
+            }
+        }
+        diagnostic.entity = Some(EntityRef {
+            kind: err.kind,
+            index,
+            id: Some(err.id.clone()),
+        });
+        diagnostic
+    }
+
+    /// Point the diagnostic at byte `offset` of `text`.
+    fn locate(
+        &mut self,
+        text: &str,
+        format: ResolvedSourceFormat,
+        lines: &LineIndex,
+        offset: usize,
+    ) {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let (mut path, token) = path_at(text, offset);
+        // A missing field is reported at the end of its object, after the last field
+        if self.message.starts_with("missing field")
+            && matches!(path.last(), Some(PathSegment::Key(_)))
+        {
+            path.pop();
+        }
+        let (start, end) = token.unwrap_or_else(|| {
+            let len = text[offset..].chars().next().map_or(0, char::len_utf8);
+            (offset, offset + len)
+        });
+        let span = lines.span(start, end);
+
+        self.entity = entity(text, format, &path);
+        self.path = Some(format_path(&path)).filter(|path| !path.is_empty());
+        self.snippet = snippet(text, &span);
+        self.span = Some(span);
+    }
+
+    /// Describe `err`, re-reading the file it happened in (as `config` says) for the details.
+    pub fn from_load_error(err: &LoadError, config: &ResolvedConfig, reader: &mut Reader) -> Self {
+        let file = file_of(err);
+        let format = err.path.as_ref().and_then(|path| {
+            let source = config
+                .source
+                .iter()
+                .find(|source| source.root == err.root)?;
+            Some(source.format_of(path))
+        });
+        let json_error = match &err.kind {
+            LoadErrorKind::Parse(parse) => parse.downcast_ref::<serde_json::Error>(),
+            _ => None,
+        };
+
+        let non_standard = match &err.kind {
+            LoadErrorKind::Parse(parse) => parse.downcast_ref::<NonStandardJson>(),
+            _ => None,
+        };
+
+        let (diagnostic, format) = match (&err.kind, format) {
+            (LoadErrorKind::Entity(entity), Some(format)) => {
+                let diagnostic = match reader.read_to_string(&file, format) {
+                    Ok(text) => Self::from_entity_error(file, &text, format, entity),
+                    Err(_) => Self::from_entity_error(file, "", format, entity),
+                };
+                (diagnostic, format)
+            }
+            (LoadErrorKind::Parse(_), Some(format)) if json_error.is_some() => {
+                let json_error = json_error.expect("matched by the guard");
+                let diagnostic = match reader.read_to_string(&file, format) {
+                    Ok(text) => Self::from_json_error(file, &text, format, json_error),
+                    Err(_) => Self::new(file, json_error.to_string()),
+                };
+                (diagnostic, format)
+            }
+            (LoadErrorKind::Parse(_), Some(format)) if non_standard.is_some() => {
+                let non_standard = non_standard.expect("matched by the guard");
+                let mut diagnostic = Self::new(file.clone(), non_standard.to_string());
+                if let (Some(first), Ok(text)) = (
+                    non_standard.uses.first(),
+                    reader.read_to_string(&file, format),
+                ) {
+                    diagnostic.found = Some(first.extension.to_string());
+                    diagnostic.snippet = snippet(&text, &first.span);
+                    diagnostic.span = Some(first.span);
+                }
+                (diagnostic, format)
+            }
+            (kind, _) => {
+                let message = match kind {
+                    LoadErrorKind::Walk(err) => err.to_string(),
+                    LoadErrorKind::Parse(err) => format!("{err:#}"),
+                    LoadErrorKind::Merge(err) => err.to_string(),
+                    LoadErrorKind::Entity(err) => err.to_string(),
+                };
+                return Self::new(file, message);
+            }
+        };
+
+        Self {
+            encoding: Some(encoding_of(&diagnostic.file, format).name()),
+            ..diagnostic
+        }
+    }
+}
+
+fn file_of(err: &LoadError) -> PathBuf {
+    match &err.path {
+        Some(path) => err.root.join(path),
+        None => err.root.clone(),
+    }
+}
+
+/// The encoding `file` is decoded with, by its byte order mark and `format`.
+fn encoding_of(file: &Path, format: ResolvedSourceFormat) -> &'static Encoding {
+    let mut head = Vec::new();
+    let _ = File::open(file).and_then(|file| file.take(4).read_to_end(&mut head));
+    Reader::resolved_encoding(&head, format)
+}
+
+/// The message of `err`, without the position serde_json appends to it.
+fn message_of(err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    match message.strip_suffix(&format!(" at line {} column {}", err.line(), err.column())) {
+        Some(message) => message.to_owned(),
+        None => message,
+    }
+}
+
+/// Deserialize the `text` of a single entity of `kind`, returning the error if it fails.
+fn deserialize_entity(
+    kind: EntityKind,
+    text: &str,
+    format: ResolvedSourceFormat,
+) -> Option<serde_json::Error> {
+    fn err<T: DeserializeOwned>(
+        text: &str,
+        format: ResolvedSourceFormat,
+    ) -> Option<serde_json::Error> {
+        Reader::deserialize_str::<T>(text, format).err()
+    }
+    match kind {
+        EntityKind::Achievements => err::<Achievements>(text, format),
+        EntityKind::Cultures => err::<Cultures>(text, format),
+        EntityKind::Decks => err::<Decks>(text, format),
+        EntityKind::Dicta => err::<Dicta>(text, format),
+        EntityKind::Elements => err::<Elements>(text, format),
+        EntityKind::Endings => err::<Endings>(text, format),
+        EntityKind::Legacies => err::<Legacies>(text, format),
+        EntityKind::Levers => err::<Levers>(text, format),
+        EntityKind::Portals => err::<Portals>(text, format),
+        EntityKind::Recipes => err::<Recipes>(text, format),
+        EntityKind::Settings => err::<Settings>(text, format),
+        EntityKind::Verbs => err::<Verbs>(text, format),
+    }
+}
+
+/// Split serde's "invalid type: <found>, expected <expected>" style messages.
+fn expected_found(message: &str) -> (Option<String>, Option<String>) {
+    if let Some(field) = message.strip_prefix("missing field ") {
+        return (Some(format!("field {field}")), None);
+    }
+    let (found, expected) = match message.split_once(", expected ") {
+        Some((found, expected)) => (found, Some(expected.to_owned())),
+        None => (message, None),
+    };
+    let found = [
+        ("invalid type: ", ""),
+        ("invalid value: ", ""),
+        ("invalid length ", "length "),
+        ("unknown field ", "field "),
+        ("unknown variant ", "variant "),
+    ]
+    .into_iter()
+    .find_map(|(prefix, kind)| {
+        let found = found.strip_prefix(prefix)?;
+        // "unknown field `x`, there are no fields"
+        let found = found.split(", ").next().unwrap_or(found);
+        Some(format!("{kind}{found}"))
+    });
+    (expected, found)
+}
+
+fn entity(text: &str, format: ResolvedSourceFormat, path: &[PathSegment]) -> Option<EntityRef> {
+    let [PathSegment::Key(kind), PathSegment::Index(index), ..] = path else {
+        return None;
+    };
+    let kind: EntityKind = kind.parse().ok()?;
+    let id = entity_spans(text)
+        .get(&kind)
+        .and_then(|spans| spans.get(*index))
+        .and_then(|span| Reader::deserialize_str::<Value>(&text[span.start..span.end], format).ok())
+        .and_then(|entity| {
+            let entity = entity.as_object()?;
+            let (_, id) = entity
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("id"))?;
+            id.as_str().map(ToOwned::to_owned)
+        });
+    Some(EntityRef {
+        kind,
+        index: Some(*index),
+        id,
+    })
+}
+
+/// The lines of `span` and one line of context around them, at most 7 lines.
+fn snippet(text: &str, span: &Span) -> Vec<SnippetLine> {
+    let first = span.line.saturating_sub(1).max(1);
+    let last = (span.end_line + 1).min(first + 6);
+    let mut start = 0;
+    let mut lines = Vec::new();
+    for (i, text) in text.split('\n').enumerate().take(last) {
+        if i + 1 >= first {
+            lines.push(SnippetLine {
+                line: i + 1,
+                start,
+                text: text.trim_end_matches('\r').to_owned(),
+            });
+        }
+        start += text.len() + 1;
+    }
+    lines
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        match &self.span {
+            Some(span) => writeln!(f, "  --> {}:{span}", self.file.display())?,
+            None => writeln!(f, "  --> {}", self.file.display())?,
+        }
+
+        let width = self
+            .snippet
+            .last()
+            .map_or(1, |line| line.line.to_string().len());
+        let pad = " ".repeat(width);
+        if let Some(entity) = &self.entity {
+            write!(f, "{pad} = entity: {}", entity.kind)?;
+            if let Some(index) = entity.index {
+                write!(f, "[{index}]")?;
+            }
+            match &entity.id {
+                Some(id) => writeln!(f, " {id:?}")?,
+                None => writeln!(f)?,
+            }
+        }
+        let notes = [
+            ("path", &self.path),
+            ("expected", &self.expected),
+            ("found", &self.found),
+        ];
+        for (name, note) in notes {
+            if let Some(note) = note {
+                writeln!(f, "{pad} = {name}: {note}")?;
+            }
+        }
+        if let Some(encoding) = self.encoding {
+            writeln!(f, "{pad} = encoding: {encoding}")?;
+        }
+
+        let Some(span) = &self.span else {
+            return Ok(());
+        };
+        writeln!(f, "{pad} |")?;
+        for line in &self.snippet {
+            writeln!(f, "{:>width$} | {}", line.line, line.text)?;
+            // Underline the part of the line inside the span
+            let (start, end) = (line.start, line.start + line.text.len());
+            if line.line < span.line || line.line > span.end_line {
+                continue;
+            }
+            let from = span.start.max(start) - start;
+            let to = span.end.min(end).max(span.start.max(start)) - start;
+            let indent: String = line.text[..from]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let marks = "^".repeat(line.text[from..to].chars().count().max(1));
+            writeln!(f, "{pad} | {indent}{marks}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compendium::SourceFile, merge::RawCompendium, unknown::UnknownFields};
+
+    const FORMAT: ResolvedSourceFormat = ResolvedSourceFormat {
+        autodetect: true,
+        encoding: None,
+        allow_trailing_comma: false,
+        strict: false,
+    };
+
+    /// Load `files` in order and describe the first entity error, for the text of the last file.
+    fn entity_diagnostic(files: &[&str], unknown_fields: UnknownFields) -> Diagnostic {
+        let mut raw = RawCompendium::new();
+        for (i, text) in files.iter().enumerate() {
+            let source = SourceFile {
+                root: "root".into(),
+                path: format!("{i}.json").into(),
+            };
+            let data = Reader::deserialize_str(text, FORMAT).unwrap();
+            raw.insert_data(&source, data, &entity_spans(text)).unwrap();
+        }
+        let err = raw.into_compendium(unknown_fields).unwrap_err();
+        let text = files.last().unwrap();
+        Diagnostic::from_entity_error(err.source.full_path(), text, FORMAT, &err)
+    }
+
+    #[test]
+    fn entity_type_error() {
+        let text = r#"{
+  "elements": [
+    { "id": "nail" },
+    {
+      "id": "hammer",
+      "slots": [{
+        "actionid": "work",
+        "id": "head",
+        "required": { "edge": "x" }
+      }]
+    }
+  ]
+}"#;
+        let diagnostic = entity_diagnostic(&[text], UnknownFields::Strict);
+        assert_eq!(
+            diagnostic.path.as_deref(),
+            Some("elements[1].slots[0].required.edge")
+        );
+        let span = diagnostic.span.unwrap();
+        assert_eq!((span.line, span.column), (9, 31));
+        assert_eq!(&text[span.start..span.end], r#""x""#);
+        let entity = diagnostic.entity.unwrap();
+        assert_eq!(
+            (entity.index, entity.id.as_deref()),
+            (Some(1), Some("hammer"))
+        );
+        assert_eq!(diagnostic.found.as_deref(), Some(r#"string "x""#));
+        assert_eq!(diagnostic.expected.as_deref(), Some("u32"));
+        assert!(diagnostic.snippet.iter().any(|line| line.line == 9));
+    }
+
+    #[test]
+    fn entity_missing_field() {
+        let text = r#"{ "elements": [{ "id": "hammer", "slots": [{ "id": "head" }] }] }"#;
+        let diagnostic = entity_diagnostic(&[text], UnknownFields::Capture);
+        assert_eq!(diagnostic.message, "missing field `actionid`");
+        assert_eq!(diagnostic.path.as_deref(), Some("elements[0].slots[0]"));
+        assert!(diagnostic.span.is_some());
+    }
+
+    #[test]
+    fn merged_entity_error() {
+        // The patch is fine on its own, only the merged entity fails
+        let base = r#"{ "elements": [{ "id": "hammer", "aspects": { "edge": 1 } }] }"#;
+        let patch = r#"{
+  "elements": [{ "id": "hammer", "aspects$add": { "edge": "x" } }]
+}"#;
+        let diagnostic = entity_diagnostic(&[base, patch], UnknownFields::Strict);
+        assert_eq!(diagnostic.path, None);
+        let span = diagnostic.span.unwrap();
+        assert_eq!((span.line, span.column), (2, 16));
+        let entity = diagnostic.entity.unwrap();
+        assert_eq!((entity.index, entity.id.as_deref()), (None, Some("hammer")));
+    }
+}
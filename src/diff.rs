@@ -0,0 +1,441 @@
+//! Compare the content of two [`Compendium`]s, like two versions of the game.
+//!
+//! Entities are matched by kind and id, and changed entities are compared field by field on their
+//! serialized form. Lists whose items all have an `id` (like `linked`) are matched by that id,
+//! lists of scalars as sets and every other list by index.
+
+use crate::{
+    compendium::{Compendium, SourceFile, Sourced},
+    data::{Entity, EntityKind},
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Change {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Changed => "changed",
+        }
+    }
+
+    fn sign(self) -> char {
+        match self {
+            Change::Added => '+',
+            Change::Removed => '-',
+            Change::Changed => '~',
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An entity that differs between the two compendiums.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityDiff<'a> {
+    pub kind: EntityKind,
+    pub id: &'a str,
+    pub change: Change,
+    /// Where the entity was defined in the old compendium.
+    pub old_source: Option<&'a SourceFile>,
+    /// Where the entity is defined in the new compendium.
+    pub new_source: Option<&'a SourceFile>,
+    /// The changed fields, only for [`Change::Changed`].
+    pub fields: Vec<FieldDiff>,
+}
+
+impl Display for EntityDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}[{}]", self.change.sign(), self.kind, self.id)?;
+        for field in &self.fields {
+            writeln!(f, "    {field}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A single value that differs, [`None`] on the side it doesn't exist on.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDiff {
+    /// The path inside the entity, like `aspects.edge` or `linked[other.recipe].chance`.
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl FieldDiff {
+    pub fn change(&self) -> Change {
+        match (&self.old, &self.new) {
+            (None, _) => Change::Added,
+            (_, None) => Change::Removed,
+            _ => Change::Changed,
+        }
+    }
+}
+
+impl Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{}: {old} -> {new}", self.path),
+            (None, Some(new)) => write!(f, "{}: + {new}", self.path),
+            (Some(old), None) => write!(f, "{}: - {old}", self.path),
+            (None, None) => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// Every entity that differs between two compendiums, ordered by kind and id.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct ContentDiff<'a> {
+    pub entities: Vec<EntityDiff<'a>>,
+}
+
+impl<'a> ContentDiff<'a> {
+    pub fn new(old: &'a Compendium, new: &'a Compendium) -> Self {
+        let mut entities = Vec::new();
+        diff_all(&mut entities, &old.achievements, &new.achievements);
+        diff_all(&mut entities, &old.cultures, &new.cultures);
+        diff_all(&mut entities, &old.decks, &new.decks);
+        diff_all(&mut entities, &old.dicta, &new.dicta);
+        diff_all(&mut entities, &old.elements, &new.elements);
+        diff_all(&mut entities, &old.endings, &new.endings);
+        diff_all(&mut entities, &old.legacies, &new.legacies);
+        diff_all(&mut entities, &old.levers, &new.levers);
+        diff_all(&mut entities, &old.portals, &new.portals);
+        diff_all(&mut entities, &old.recipes, &new.recipes);
+        diff_all(&mut entities, &old.settings, &new.settings);
+        diff_all(&mut entities, &old.verbs, &new.verbs);
+        Self { entities }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// How many entities were added, removed and changed.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let count = |change| {
+            (self.entities.iter())
+                .filter(|entity| entity.change == change)
+                .count()
+        };
+        (
+            count(Change::Added),
+            count(Change::Removed),
+            count(Change::Changed),
+        )
+    }
+}
+
+impl Display for ContentDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entity in &self.entities {
+            write!(f, "{entity}")?;
+        }
+        Ok(())
+    }
+}
+
+fn diff_all<'a, T: Entity + Serialize>(
+    out: &mut Vec<EntityDiff<'a>>,
+    old: &'a BTreeMap<String, Sourced<T>>,
+    new: &'a BTreeMap<String, Sourced<T>>,
+) {
+    let mut ids: Vec<&str> = old.keys().chain(new.keys()).map(String::as_str).collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    for id in ids {
+        let (old, new) = (old.get(id), new.get(id));
+        let (change, fields) = match (old, new) {
+            (None, None) => continue,
+            (None, Some(_)) => (Change::Added, Vec::new()),
+            (Some(_), None) => (Change::Removed, Vec::new()),
+            (Some(old), Some(new)) => {
+                let mut fields = Vec::new();
+                diff_values(&mut fields, "", to_value(&old.value), to_value(&new.value));
+                if fields.is_empty() {
+                    continue;
+                }
+                (Change::Changed, fields)
+            }
+        };
+        out.push(EntityDiff {
+            kind: T::KIND,
+            id,
+            change,
+            old_source: old.map(|old| &old.source),
+            new_source: new.map(|new| &new.source),
+            fields,
+        });
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("entities always serialize")
+}
+
+/// Drop the `null` fields of added or removed objects, which are only unset [`Option`]s.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+        value => value,
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_owned(),
+        false => format!("{path}.{key}"),
+    }
+}
+
+fn diff_values(out: &mut Vec<FieldDiff>, path: &str, old: Value, new: Value) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(mut old), Value::Object(new)) => {
+            for (key, new) in new {
+                let path = join(path, &key);
+                match old.remove(&key) {
+                    Some(old) => diff_values(out, &path, old, new),
+                    None => out.push(FieldDiff {
+                        path,
+                        old: None,
+                        new: Some(without_nulls(new)),
+                    }),
+                }
+            }
+            for (key, old) in old {
+                out.push(FieldDiff {
+                    path: join(path, &key),
+                    old: Some(without_nulls(old)),
+                    new: None,
+                });
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => diff_arrays(out, path, old, new),
+        // An unset `Option` serializes as `null`, so a value that becomes `null` was removed
+        (old, new) => out.push(FieldDiff {
+            path: path.to_owned(),
+            old: Some(old).filter(|old| !old.is_null()).map(without_nulls),
+            new: Some(new).filter(|new| !new.is_null()).map(without_nulls),
+        }),
+    }
+}
+
+fn diff_arrays(out: &mut Vec<FieldDiff>, path: &str, old: Vec<Value>, new: Vec<Value>) {
+    let item_id = |item: &Value| Some(item.get("id")?.as_str()?.to_owned());
+    let by_id = |items: &[Value]| -> Option<BTreeMap<String, Value>> {
+        let ids = items.iter().map(item_id).collect::<Option<Vec<_>>>()?;
+        let map: BTreeMap<_, _> = ids.into_iter().zip(items.iter().cloned()).collect();
+        // Repeated ids can't be matched
+        (map.len() == items.len()).then_some(map)
+    };
+    let scalars = |items: &[Value]| {
+        (items.iter()).all(|item| !matches!(item, Value::Array(_) | Value::Object(_)))
+    };
+
+    if let (Some(mut old), Some(new)) = (by_id(&old), by_id(&new)) {
+        for (id, new) in new {
+            let path = format!("{path}[{id}]");
+            match old.remove(&id) {
+                Some(old) => diff_values(out, &path, old, new),
+                None => out.push(FieldDiff {
+                    path,
+                    old: None,
+                    new: Some(without_nulls(new)),
+                }),
+            }
+        }
+        for (id, old) in old {
+            out.push(FieldDiff {
+                path: format!("{path}[{id}]"),
+                old: Some(without_nulls(old)),
+                new: None,
+            });
+        }
+    } else if scalars(&old) && scalars(&new) {
+        let before = out.len();
+        for item in old.iter().filter(|item| !new.contains(item)) {
+            out.push(FieldDiff {
+                path: path.to_owned(),
+                old: Some(item.clone()),
+                new: None,
+            });
+        }
+        for item in new.iter().filter(|item| !old.contains(item)) {
+            out.push(FieldDiff {
+                path: path.to_owned(),
+                old: None,
+                new: Some(item.clone()),
+            });
+        }
+        // Only the order (or how often an item repeats) changed
+        if out.len() == before {
+            out.push(FieldDiff {
+                path: path.to_owned(),
+                old: Some(Value::Array(old)),
+                new: Some(Value::Array(new)),
+            });
+        }
+    } else {
+        let len = old.len().max(new.len());
+        let (mut old, mut new) = (old.into_iter(), new.into_iter());
+        for i in 0..len {
+            let path = format!("{path}[{i}]");
+            match (old.next(), new.next()) {
+                (Some(old), Some(new)) => diff_values(out, &path, old, new),
+                (old, new) => out.push(FieldDiff {
+                    path,
+                    old: old.map(without_nulls),
+                    new: new.map(without_nulls),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Data;
+    use serde_json::json;
+
+    fn compendium(data: Value) -> Compendium {
+        let mut compendium = Compendium::new();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "test.json".into(),
+        };
+        compendium.insert_data(&source, serde_json::from_value::<Data>(data).unwrap());
+        compendium
+    }
+
+    /// The fields of the only changed entity, with their change.
+    fn fields(old: Value, new: Value) -> Vec<(Change, String)> {
+        let (old, new) = (compendium(old), compendium(new));
+        let diff = ContentDiff::new(&old, &new);
+        let [entity] = &diff.entities[..] else {
+            panic!("{diff}");
+        };
+        assert_eq!(entity.change, Change::Changed);
+        (entity.fields.iter())
+            .map(|field| (field.change(), field.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn entities() {
+        let old = compendium(json!({
+            "elements": [{ "id": "a" }, { "id": "b" }, { "id": "c", "label": "C" }],
+        }));
+        let new = compendium(json!({
+            "elements": [{ "id": "b" }, { "id": "c", "label": "C!" }, { "id": "d" }],
+        }));
+        let diff = ContentDiff::new(&old, &new);
+        assert_eq!(diff.counts(), (1, 1, 1));
+        assert_eq!(
+            diff.to_string(),
+            "- elements[a]\n~ elements[c]\n    label: \"C\" -> \"C!\"\n+ elements[d]\n"
+        );
+        assert!(ContentDiff::new(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn unset_options() {
+        assert_eq!(
+            fields(
+                json!({ "elements": [{ "id": "a", "label": "A" }] }),
+                json!({ "elements": [{ "id": "a", "icon": "a" }] }),
+            ),
+            [
+                (Change::Added, "icon: + \"a\"".to_owned()),
+                (Change::Removed, "label: - \"A\"".to_owned()),
+            ]
+        );
+        let recipe = |linked: Value| json!({ "recipes": [{ "id": "r", "linked": [linked] }] });
+        assert_eq!(
+            fields(
+                recipe(json!({ "id": "a" })),
+                recipe(json!({ "id": "a", "expulsion": { "filter": { "x": 1 }, "limit": 1 } })),
+            ),
+            [(
+                Change::Added,
+                r#"linked[a].expulsion: + {"filter":{"x":1},"limit":1}"#.to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn lists_by_id() {
+        let recipe = |linked: Value| json!({ "recipes": [{ "id": "r", "linked": linked }] });
+        assert_eq!(
+            fields(
+                recipe(json!([{ "id": "a", "chance": 50 }, { "id": "b" }, { "id": "gone" }])),
+                recipe(json!([{ "id": "b" }, { "id": "c" }, { "id": "a", "chance": 60 }])),
+            ),
+            [
+                (Change::Changed, "linked[a].chance: 50 -> 60".to_owned()),
+                (
+                    Change::Added,
+                    r#"linked[c]: + {"additional":false,"id":"c","shuffle":false}"#.to_owned()
+                ),
+                (
+                    Change::Removed,
+                    r#"linked[gone]: - {"additional":false,"id":"gone","shuffle":false}"#
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn scalar_lists_as_sets() {
+        fn element(achievements: Value) -> Value {
+            json!({
+                "elements": [{ "id": "a", "achievements": achievements }],
+            })
+        }
+        assert_eq!(
+            fields(element(json!(["x", "y"])), element(json!(["y", "z"]))),
+            [
+                (Change::Removed, "achievements: - \"x\"".to_owned()),
+                (Change::Added, "achievements: + \"z\"".to_owned()),
+            ]
+        );
+        // Only the order changed
+        assert_eq!(
+            fields(element(json!(["x", "y"])), element(json!(["y", "x"]))),
+            [(
+                Change::Changed,
+                r#"achievements: ["x","y"] -> ["y","x"]"#.to_owned()
+            )]
+        );
+    }
+}
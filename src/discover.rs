@@ -0,0 +1,756 @@
+//! Find Book of Hours installs on Linux.
+//!
+//! Installs are searched for in this order, and the first one is used for `root = "auto"`:
+//!
+//! 1. Steam libraries, read from `libraryfolders.vdf` of every Steam root (native, Flatpak).
+//! 2. GOG installs in `~/GOG Games`.
+//! 3. Heroic installs, read from its `installed.json`.
+//! 4. Lutris installs, read from its game configs and from `~/Games`.
+//!
+//! Windows builds (like through Proton, or in a Wine prefix) keep mods in the prefix, native builds
+//! in `~/.config/unity3d`. All paths are built from a [`SearchPaths`], so a fixture directory can
+//! stand in for the home directory.
+
+use crate::config::{SourceFileConfig, SourceGlob};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    env,
+    fmt::{self, Display},
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+};
+
+pub const STEAM_APP_ID: &str = "1028310";
+
+/// The content folder, relative to the game folder.
+const CONTENT: &str = "bh_Data/StreamingAssets/bhcontent";
+/// Where the game keeps its data (like mods), relative to `~/.config` or `LocalLow` on Windows.
+const USER_DATA: &str = "Weather Factory/Book of Hours";
+/// Where the game is installed inside of a Wine prefix.
+const PREFIX_INSTALLS: [&str; 3] = [
+    "drive_c/GOG Games/Book of Hours",
+    "drive_c/Program Files (x86)/GOG Galaxy/Games/Book of Hours",
+    "drive_c/Program Files (x86)/Steam/steamapps/common/Book of Hours",
+];
+
+/// The directories installs are searched in.
+#[derive(Debug, Clone)]
+pub struct SearchPaths {
+    pub home: PathBuf,
+    /// `$XDG_DATA_HOME`, defaults to `~/.local/share`
+    pub data_home: PathBuf,
+    /// `$XDG_CONFIG_HOME`, defaults to `~/.config`
+    pub config_home: PathBuf,
+}
+
+impl SearchPaths {
+    /// The paths for the home directory `home`, ignoring the environment.
+    pub fn with_home<P: Into<PathBuf>>(home: P) -> Self {
+        let home = home.into();
+        Self {
+            data_home: home.join(".local/share"),
+            config_home: home.join(".config"),
+            home,
+        }
+    }
+
+    /// The paths of the current user, or [`None`] if `$HOME` isn't set.
+    pub fn from_env() -> Option<Self> {
+        let mut paths = Self::with_home(env::var_os("HOME")?);
+        let xdg = |var| env::var_os(var).filter(|path| !path.is_empty());
+        if let Some(data_home) = xdg("XDG_DATA_HOME") {
+            paths.data_home = data_home.into();
+        }
+        if let Some(config_home) = xdg("XDG_CONFIG_HOME") {
+            paths.config_home = config_home.into();
+        }
+        Some(paths)
+    }
+
+    fn steam_roots(&self) -> [PathBuf; 3] {
+        [
+            self.data_home.join("Steam"),
+            self.home.join(".steam/steam"),
+            (self.home).join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Store {
+    Steam,
+    Gog,
+    Heroic,
+    Lutris,
+}
+
+impl Store {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Store::Steam => "Steam",
+            Store::Gog => "GOG",
+            Store::Heroic => "Heroic",
+            Store::Lutris => "Lutris",
+        }
+    }
+}
+
+impl Display for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An installed copy of the game.
+#[derive(Debug, Clone, Serialize)]
+pub struct Install {
+    pub store: Store,
+    /// The game folder, with `bh_Data` in it.
+    pub path: PathBuf,
+    /// The Wine prefix a Windows build runs in.
+    pub prefix: Option<PathBuf>,
+    /// Where the game keeps its data, with local mods in `mods`.
+    pub user_data: Option<PathBuf>,
+    /// The Steam workshop folder, with a folder per subscribed mod.
+    pub workshop: Option<PathBuf>,
+}
+
+impl Install {
+    /// The `bhcontent` folder.
+    pub fn content(&self) -> PathBuf {
+        self.path.join(CONTENT)
+    }
+
+    pub fn core(&self) -> PathBuf {
+        self.content().join("core")
+    }
+
+    /// The content folder of every installed DLC.
+    pub fn dlc(&self) -> Vec<PathBuf> {
+        subdirectories(&self.content().join("dlc"))
+    }
+
+    /// The content folder of every local and workshop mod.
+    pub fn mods(&self) -> Vec<PathBuf> {
+        let local = self.user_data.as_ref().map(|path| path.join("mods"));
+        (local.iter().chain(&self.workshop))
+            .flat_map(|path| subdirectories(path))
+            .map(|path| path.join("content"))
+            .filter(|path| path.is_dir())
+            .collect()
+    }
+
+    /// A source for the core content, every DLC and every mod, in that order.
+    pub fn source_configs(&self) -> Vec<SourceFileConfig> {
+        let mut configs = vec![source_config(self.core())];
+        configs.extend(self.dlc().into_iter().map(source_config));
+        // Mods can keep content files at any depth
+        configs.extend(self.mods().into_iter().map(|root| SourceFileConfig {
+            glob: Some(SourceGlob::Glob("**/*.json".to_owned())),
+            ..source_config(root)
+        }));
+        configs
+    }
+}
+
+fn source_config(root: PathBuf) -> SourceFileConfig {
+    SourceFileConfig {
+        root,
+        follow_links: None,
+        max_depth: None,
+        glob: None,
+        include: None,
+        format: None,
+        files: None,
+        exclude: None,
+    }
+}
+
+/// Every install found in `paths`, in search order (see the [module docs](self)).
+pub fn find_installs(paths: &SearchPaths) -> Vec<Install> {
+    let mut installs = Vec::new();
+    steam_installs(paths, &mut installs);
+    gog_installs(paths, &mut installs);
+    heroic_installs(paths, &mut installs);
+    lutris_installs(paths, &mut installs);
+
+    let mut seen = HashSet::new();
+    installs.retain(|install| seen.insert(canonical(&install.path)));
+    installs
+}
+
+/// The sources `root = "auto"` expands into, with every option set in `template` applied to them.
+pub fn auto_sources(
+    template: &SourceFileConfig,
+    paths: &SearchPaths,
+) -> Result<Vec<SourceFileConfig>> {
+    let install = (find_installs(paths).into_iter().next())
+        .context("no Book of Hours install found for root = \"auto\"")?;
+    Ok((install.source_configs().into_iter())
+        .map(|source| overlay(template.clone(), source))
+        .collect())
+}
+
+fn overlay(template: SourceFileConfig, source: SourceFileConfig) -> SourceFileConfig {
+    let SourceFileConfig {
+        root: _,
+        follow_links,
+        max_depth,
+        glob,
+        include,
+        format,
+        files,
+        exclude,
+    } = template;
+    SourceFileConfig {
+        follow_links: follow_links.or(source.follow_links),
+        max_depth: max_depth.or(source.max_depth),
+        // `glob` and `include` can't both be set, so the glob of a mod is dropped for `include`
+        glob: glob.or(source.glob.filter(|_| include.is_none())),
+        include: include.or(source.include),
+        format: format.or(source.format),
+        files: files.or(source.files),
+        exclude: exclude.or(source.exclude),
+        root: source.root,
+    }
+}
+
+/// The install at `candidate` (or in its `game` folder), if there is one.
+fn install(
+    store: Store,
+    candidate: &Path,
+    prefix: Option<PathBuf>,
+    workshop: Option<PathBuf>,
+    paths: &SearchPaths,
+) -> Option<Install> {
+    let path = [candidate.to_owned(), candidate.join("game")]
+        .into_iter()
+        .find(|path| path.join(CONTENT).join("core").is_dir())?;
+    let prefix = prefix.filter(|_| path.join("bh.exe").is_file());
+    let user_data = match &prefix {
+        Some(prefix) => subdirectories(&prefix.join("drive_c/users"))
+            .into_iter()
+            .map(|user| user.join("AppData/LocalLow").join(USER_DATA))
+            .find(|path| path.is_dir()),
+        None => {
+            Some(paths.config_home.join("unity3d").join(USER_DATA)).filter(|path| path.is_dir())
+        }
+    };
+    Some(Install {
+        store,
+        path,
+        prefix,
+        user_data,
+        workshop,
+    })
+}
+
+fn steam_installs(paths: &SearchPaths, out: &mut Vec<Install>) {
+    let mut libraries = Vec::new();
+    for root in paths.steam_roots() {
+        if !root.join("steamapps").is_dir() {
+            continue;
+        }
+        for file in ["steamapps/libraryfolders.vdf", "config/libraryfolders.vdf"] {
+            let Some(Vdf::Map(folders)) = read_vdf(&root.join(file))
+                .as_ref()
+                .and_then(|vdf| vdf.get("libraryfolders"))
+                .cloned()
+            else {
+                continue;
+            };
+            for (key, folder) in folders {
+                // Skip entries like `contentstatsid`
+                if key.parse::<u32>().is_err() {
+                    continue;
+                }
+                // Older versions only have the path instead of a map with it
+                let path = match &folder {
+                    Vdf::String(path) => Some(path.as_str()),
+                    folder => folder.get("path").and_then(Vdf::as_str),
+                };
+                libraries.extend(path.map(PathBuf::from));
+            }
+        }
+        libraries.push(root);
+    }
+
+    let mut seen = HashSet::new();
+    for library in libraries {
+        if !seen.insert(canonical(&library)) {
+            continue;
+        }
+        let steamapps = library.join("steamapps");
+        let manifest = read_vdf(&steamapps.join(format!("appmanifest_{STEAM_APP_ID}.acf")));
+        let install_dir = (manifest.as_ref())
+            .and_then(|manifest| manifest.get("AppState")?.get("installdir")?.as_str())
+            .unwrap_or("Book of Hours");
+        let prefix = steamapps.join("compatdata").join(STEAM_APP_ID).join("pfx");
+        let workshop = steamapps.join("workshop/content").join(STEAM_APP_ID);
+        out.extend(install(
+            Store::Steam,
+            &steamapps.join("common").join(install_dir),
+            Some(prefix).filter(|path| path.is_dir()),
+            Some(workshop).filter(|path| path.is_dir()),
+            paths,
+        ));
+    }
+}
+
+fn gog_installs(paths: &SearchPaths, out: &mut Vec<Install>) {
+    let candidate = paths.home.join("GOG Games/Book of Hours");
+    out.extend(install(Store::Gog, &candidate, None, None, paths));
+}
+
+fn heroic_installs(paths: &SearchPaths, out: &mut Vec<Install>) {
+    let roots = [
+        paths.config_home.join("heroic"),
+        (paths.home).join(".var/app/com.heroicgameslauncher.hgl/config/heroic"),
+    ];
+    for root in roots {
+        let Some(installed) = read_json(&root.join("gog_store/installed.json")) else {
+            continue;
+        };
+        let games = installed.get("installed").and_then(Value::as_array);
+        for game in games.into_iter().flatten() {
+            let (Some(app_name), Some(path)) = (
+                game.get("appName").and_then(Value::as_str),
+                game.get("install_path").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            let prefix = read_json(&root.join("GamesConfig").join(format!("{app_name}.json")))
+                .and_then(|config| Some(config.get(app_name)?.get("winePrefix")?.as_str()?.into()));
+            out.extend(install(Store::Heroic, Path::new(path), prefix, None, paths));
+        }
+    }
+}
+
+fn lutris_installs(paths: &SearchPaths, out: &mut Vec<Install>) {
+    for games in [
+        paths.data_home.join("lutris/games"),
+        paths.config_home.join("lutris/games"),
+    ] {
+        for config in files(&games) {
+            let Ok(config) = read_to_string(config) else {
+                continue;
+            };
+            // Only the few keys needed, instead of parsing the whole yaml
+            let value = |key: &str| {
+                (config.lines())
+                    .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix(':'))
+                    .map(|value| PathBuf::from(value.trim().trim_matches(['"', '\''])))
+                    .filter(|path| path.is_absolute())
+            };
+            let prefix = value("prefix");
+            let candidates = (value("exe").and_then(|exe| Some(exe.parent()?.to_owned())))
+                .into_iter()
+                .chain(prefix.iter().flat_map(|prefix| prefix_installs(prefix)));
+            for candidate in candidates {
+                out.extend(install(
+                    Store::Lutris,
+                    &candidate,
+                    prefix.clone(),
+                    None,
+                    paths,
+                ));
+            }
+        }
+    }
+
+    // The default install folders, like `~/Games/book-of-hours` or `~/Games/gog/book-of-hours`
+    let games = paths.home.join("Games");
+    for dir in subdirectories(&games)
+        .into_iter()
+        .chain(subdirectories(&games.join("gog")))
+    {
+        match dir.join("drive_c").is_dir() {
+            true => {
+                for candidate in prefix_installs(&dir) {
+                    out.extend(install(
+                        Store::Lutris,
+                        &candidate,
+                        Some(dir.clone()),
+                        None,
+                        paths,
+                    ));
+                }
+            }
+            false => out.extend(install(Store::Lutris, &dir, None, None, paths)),
+        }
+    }
+}
+
+fn prefix_installs(prefix: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    PREFIX_INSTALLS.iter().map(|path| prefix.join(path))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<_> = read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    entries(dir)
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+fn files(dir: &Path) -> Vec<PathBuf> {
+    entries(dir)
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&read_to_string(path).ok()?).ok()
+}
+
+fn read_vdf(path: &Path) -> Option<Vdf> {
+    Vdf::parse(&read_to_string(path).ok()?)
+}
+
+/// A value in Valve's KeyValues text format, which `libraryfolders.vdf` and app manifests use.
+#[derive(Debug, Clone)]
+enum Vdf {
+    String(String),
+    Map(Vec<(String, Vdf)>),
+}
+
+enum Token {
+    Open,
+    Close,
+    String(String),
+}
+
+impl Vdf {
+    fn parse(text: &str) -> Option<Self> {
+        Self::parse_map(&mut tokenize(text).into_iter(), false)
+    }
+
+    fn parse_map(tokens: &mut impl Iterator<Item = Token>, nested: bool) -> Option<Self> {
+        let mut entries = Vec::new();
+        loop {
+            match tokens.next() {
+                None if !nested => break,
+                Some(Token::Close) if nested => break,
+                Some(Token::String(key)) => {
+                    let value = match tokens.next()? {
+                        Token::Open => Self::parse_map(tokens, true)?,
+                        Token::String(value) => Vdf::String(value),
+                        Token::Close => return None,
+                    };
+                    entries.push((key, value));
+                }
+                _ => return None,
+            }
+        }
+        Some(Vdf::Map(entries))
+    }
+
+    /// The value of `key`, which is case insensitive like in Steam.
+    fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Vdf::Map(entries) => (entries.iter())
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value),
+            Vdf::String(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Vdf::String(value) => Some(value),
+            Vdf::Map(_) => None,
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '"' => {
+                let mut string = String::new();
+                while let Some(char) = chars.next() {
+                    match char {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some(char) => string.push(char),
+                            None => break,
+                        },
+                        char => string.push(char),
+                    }
+                }
+                tokens.push(Token::String(string));
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for char in chars.by_ref() {
+                    if char == '\n' {
+                        break;
+                    }
+                }
+            }
+            char if char.is_whitespace() => (),
+            char => {
+                let mut string = String::from(char);
+                while let Some(&char) = chars.peek() {
+                    if char.is_whitespace() || matches!(char, '{' | '}' | '"') {
+                        break;
+                    }
+                    string.push(char);
+                    chars.next();
+                }
+                tokens.push(Token::String(string));
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, temp_dir::TempDir};
+    use std::fs::{create_dir_all, write};
+
+    /// An empty home directory for the fixture tree of a test.
+    fn home(name: &str) -> TempDir {
+        TempDir::new(&format!("discover-{name}"))
+    }
+
+    fn file(path: &Path, contents: &str) {
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(path, contents).unwrap();
+    }
+
+    /// A game folder with the core content, a DLC and (for Windows builds) `bh.exe`.
+    fn game(path: &Path, windows: bool) {
+        create_dir_all(path.join(CONTENT).join("core")).unwrap();
+        create_dir_all(path.join(CONTENT).join("dlc/DLC_HOL")).unwrap();
+        if windows {
+            file(&path.join("bh.exe"), "");
+        }
+    }
+
+    /// A mod folder in `mods` with its content.
+    fn mod_content(mods: &Path, name: &str) -> PathBuf {
+        let content = mods.join(name).join("content");
+        create_dir_all(&content).unwrap();
+        content
+    }
+
+    fn prefix_user_data(prefix: &Path) -> PathBuf {
+        (prefix.join("drive_c/users/steamuser/AppData/LocalLow")).join(USER_DATA)
+    }
+
+    fn only_install(home: &Path) -> Install {
+        let installs = find_installs(&SearchPaths::with_home(home));
+        assert_eq!(installs.len(), 1, "{installs:#?}");
+        installs.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn steam_library_folders() {
+        let home = home("steam");
+        let steam = home.join(".local/share/Steam");
+        let library = home.join("library");
+        file(
+            &steam.join("steamapps/libraryfolders.vdf"),
+            &format!(
+                r#"// libraries
+"libraryfolders"
+{{
+    "contentstatsid"    "123"
+    "0"
+    {{
+        "path"    "{}"
+        "apps"    {{ "{STEAM_APP_ID}"    "1" }}
+    }}
+}}"#,
+                library.display()
+            ),
+        );
+        let steamapps = library.join("steamapps");
+        file(
+            &steamapps.join(format!("appmanifest_{STEAM_APP_ID}.acf")),
+            "\"AppState\" { \"appid\" \"1028310\" \"InstallDir\" \"BoH\" }",
+        );
+        game(&steamapps.join("common/BoH"), true);
+        let prefix = steamapps.join("compatdata").join(STEAM_APP_ID).join("pfx");
+        let local = mod_content(&prefix_user_data(&prefix).join("mods"), "local");
+        let workshop = steamapps.join("workshop/content").join(STEAM_APP_ID);
+        let subscribed = mod_content(&workshop, "2961017888");
+
+        let install = only_install(&home);
+        assert_eq!(install.store, Store::Steam);
+        assert_eq!(install.path, steamapps.join("common/BoH"));
+        assert_eq!(install.prefix, Some(prefix.clone()));
+        assert_eq!(install.user_data, Some(prefix_user_data(&prefix)));
+        assert_eq!(install.workshop, Some(workshop));
+        assert_eq!(install.mods(), [local, subscribed]);
+    }
+
+    #[test]
+    fn steam_old_library_folders() {
+        let home = home("steam-old");
+        let steam = home.join(".steam/steam");
+        let library = home.join("library");
+        file(
+            &steam.join("steamapps/libraryfolders.vdf"),
+            &format!(
+                "\"LibraryFolders\"\n{{\n\t\"TimeNextStatsReport\"\t\"1\"\n\t\"1\"\t\"{}\"\n}}",
+                library.display()
+            ),
+        );
+        // Without an app manifest, the default install folder is used
+        game(&library.join("steamapps/common/Book of Hours"), false);
+        let user_data = home.join(".config/unity3d").join(USER_DATA);
+        let local = mod_content(&user_data.join("mods"), "local");
+
+        let install = only_install(&home);
+        assert_eq!(install.store, Store::Steam);
+        assert_eq!(install.path, library.join("steamapps/common/Book of Hours"));
+        assert_eq!(install.prefix, None);
+        assert_eq!(install.user_data, Some(user_data));
+        assert_eq!(install.workshop, None);
+        assert_eq!(install.mods(), [local]);
+    }
+
+    #[test]
+    fn heroic() {
+        let home = home("heroic");
+        let heroic = home.join(".config/heroic");
+        let path = home.join("Heroic/Book of Hours");
+        let prefix = home.join("Heroic/Prefixes/Book of Hours");
+        file(
+            &heroic.join("gog_store/installed.json"),
+            &serde_json::json!({
+                "installed": [
+                    { "appName": "1234", "install_path": path },
+                    { "appName": "5678" },
+                ],
+            })
+            .to_string(),
+        );
+        file(
+            &heroic.join("GamesConfig/1234.json"),
+            &serde_json::json!({ "1234": { "winePrefix": prefix } }).to_string(),
+        );
+        game(&path, true);
+        create_dir_all(prefix_user_data(&prefix)).unwrap();
+
+        let install = only_install(&home);
+        assert_eq!(install.store, Store::Heroic);
+        assert_eq!(install.path, path);
+        assert_eq!(install.prefix, Some(prefix.clone()));
+        assert_eq!(install.user_data, Some(prefix_user_data(&prefix)));
+        assert_eq!(install.dlc(), [path.join(CONTENT).join("dlc/DLC_HOL")]);
+    }
+
+    #[test]
+    fn lutris() {
+        let home = home("lutris");
+        let prefix = home.join("prefixes/book-of-hours");
+        let path = prefix.join(PREFIX_INSTALLS[0]);
+        file(
+            &home.join(".local/share/lutris/games/book-of-hours-1.yml"),
+            &format!(
+                "game:\n  prefix: '{}'\n  exe: relative/bh.exe\nwine:\n  version: lutris\n",
+                prefix.display()
+            ),
+        );
+        game(&path, true);
+        create_dir_all(prefix_user_data(&prefix)).unwrap();
+        // The default install folder of a native build
+        let native = home.join("Games/gog/book-of-hours/game");
+        game(&native, false);
+
+        let installs = find_installs(&SearchPaths::with_home(&*home));
+        assert_eq!(installs.len(), 2, "{installs:#?}");
+        assert_eq!(installs[0].store, Store::Lutris);
+        assert_eq!(installs[0].path, path);
+        assert_eq!(installs[0].prefix, Some(prefix.clone()));
+        assert_eq!(installs[0].user_data, Some(prefix_user_data(&prefix)));
+        assert_eq!(installs[1].store, Store::Lutris);
+        assert_eq!(installs[1].path, native);
+        assert_eq!(installs[1].prefix, None);
+    }
+
+    #[test]
+    fn root_auto() {
+        let home = home("auto");
+        let paths = SearchPaths::with_home(&*home);
+        let config: Config =
+            toml::from_str("[[source]]\nroot = \"auto\"\nexclude = [\"cultures\"]").unwrap();
+        assert!(config.clone().resolve_in(Some(&paths)).is_err());
+        assert!(config.clone().resolve_in(None).is_err());
+
+        let path = home.join("GOG Games/Book of Hours");
+        game(&path, false);
+        let local = mod_content(
+            &paths
+                .config_home
+                .join("unity3d")
+                .join(USER_DATA)
+                .join("mods"),
+            "local",
+        );
+        let resolved = config.resolve_in(Some(&paths)).unwrap();
+        let roots: Vec<_> = (resolved.source.iter())
+            .map(|source| source.root.clone())
+            .collect();
+        assert_eq!(
+            roots,
+            [
+                path.join(CONTENT).join("core"),
+                path.join(CONTENT).join("dlc/DLC_HOL"),
+                local,
+            ]
+        );
+        for source in &resolved.source {
+            // Paths are relative to the root
+            assert!(source.is_excluded(Path::new("cultures"), true));
+            assert!(!source.is_excluded(Path::new("elements"), true));
+        }
+    }
+
+    #[test]
+    fn vdf() {
+        let vdf = Vdf::parse("\"a\" { // comment\n\"B\" \"x\\\"y\" c { } }").unwrap();
+        let a = vdf.get("A").unwrap();
+        assert_eq!(a.get("b").and_then(Vdf::as_str), Some("x\"y"));
+        assert!(matches!(a.get("c"), Some(Vdf::Map(entries)) if entries.is_empty()));
+
+        for malformed in [
+            "\"a\" {",
+            "}",
+            "\"a\"",
+            "\"a\" { \"b\" }",
+            "{ }",
+            "\"a\" } ",
+        ] {
+            assert!(Vdf::parse(malformed).is_none(), "{malformed}");
+        }
+    }
+}
@@ -0,0 +1,439 @@
+//! Compare the keys the raw content files use with the keys [`crate::data`] models.
+//!
+//! Every entity is walked twice: once through the data model, with a [`Tracked`] deserializer that
+//! records what the model expects at each key path, and once as plain JSON, counting what is
+//! there. Key paths are normalized so they can be compared across entities: array items become
+//! `[]` and the keys of maps the model indexes by id become `*`, like `slots[].required.*`.
+//!
+//! Merge operators (see [`crate::merge`]) are stripped from keys before either walk, and
+//! `$remove` keys are skipped, since they hold keys instead of values.
+
+use crate::{
+    data::{
+        Achievements, Cultures, Decks, Dicta, Elements, Endings, EntityKind, Legacies, Levers,
+        Portals, Recipes, Settings, Verbs,
+    },
+    merge::MergeOp,
+    span::PathSegment,
+    track::{join, At, Hook, Tracked},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+    mem,
+};
+
+/// How many distinct example values are kept per key path.
+const EXAMPLES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonType {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => JsonType::Null,
+            Value::Bool(_) => JsonType::Bool,
+            Value::Number(_) => JsonType::Number,
+            Value::String(_) => JsonType::String,
+            Value::Array(_) => JsonType::Array,
+            Value::Object(_) => JsonType::Object,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JsonType::Null => "null",
+            JsonType::Bool => "bool",
+            JsonType::Number => "number",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        }
+    }
+}
+
+impl Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What the data model asks the deserializer for at a key path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expected {
+    Struct,
+    Map,
+    Seq,
+    Enum,
+    Bool,
+    Number,
+    String,
+    Unit,
+    /// The model decides by the value, like [`crate::data::StringOrStruct`].
+    Any,
+}
+
+impl Expected {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Expected::Struct => "struct",
+            Expected::Map => "map",
+            Expected::Seq => "seq",
+            Expected::Enum => "enum",
+            Expected::Bool => "bool",
+            Expected::Number => "number",
+            Expected::String => "string",
+            Expected::Unit => "unit",
+            Expected::Any => "any",
+        }
+    }
+
+    /// Whether values of type `ty` can deserialize as this.
+    pub fn accepts(self, ty: JsonType) -> bool {
+        match self {
+            Expected::Struct | Expected::Map => ty == JsonType::Object,
+            Expected::Seq => ty == JsonType::Array,
+            Expected::Enum => matches!(ty, JsonType::String | JsonType::Object),
+            Expected::Bool => ty == JsonType::Bool,
+            Expected::Number => ty == JsonType::Number,
+            Expected::String => ty == JsonType::String,
+            Expected::Unit => ty == JsonType::Null,
+            Expected::Any => true,
+        }
+    }
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The drift of every entity kind that appeared in the content files.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriftReport {
+    pub kinds: BTreeMap<EntityKind, KindDrift>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KindDrift {
+    /// How many entities of this kind were analyzed.
+    pub entities: usize,
+    /// Every key path that appeared, see the [module docs](self) for the format.
+    pub keys: BTreeMap<String, KeyDrift>,
+    /// Key paths the model knows but no entity used.
+    ///
+    /// Only fields of structs that appeared are listed. Fields that only differ in case are
+    /// usually aliases of each other, so they count as one.
+    pub unseen: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeyDrift {
+    /// How often the key appeared.
+    pub count: usize,
+    pub types: BTreeMap<JsonType, usize>,
+    /// A few distinct values, only kept for scalars.
+    pub examples: Vec<Value>,
+    /// Whether the data model has this key.
+    pub modelled: bool,
+    pub expected: BTreeSet<Expected>,
+    /// Whether the model accepts `null` (it is an [`Option`]).
+    pub nullable: bool,
+    /// Why values at this key failed to deserialize, and how often.
+    pub mismatches: BTreeMap<String, usize>,
+}
+
+impl KeyDrift {
+    /// The observed types the model doesn't accept.
+    pub fn unexpected_types(&self) -> impl Iterator<Item = JsonType> + '_ {
+        self.types.keys().copied().filter(|&ty| {
+            self.modelled
+                && !(self.nullable && ty == JsonType::Null)
+                && !self.expected.iter().any(|expected| expected.accepts(ty))
+        })
+    }
+
+    /// Whether values at this key don't fit the model.
+    pub fn mismatched(&self) -> bool {
+        !self.mismatches.is_empty() || self.unexpected_types().next().is_some()
+    }
+}
+
+impl DriftReport {
+    /// Analyze every entity of `files`, which are the raw content files.
+    ///
+    /// Top-level keys that aren't an [`EntityKind`] and entities that aren't objects are skipped.
+    pub fn build(files: impl IntoIterator<Item = Map<String, Value>>) -> Self {
+        let mut kinds: BTreeMap<EntityKind, KindState> = BTreeMap::new();
+        for file in files {
+            for (key, entities) in file {
+                let (Ok(kind), Value::Array(entities)) = (key.parse::<EntityKind>(), entities)
+                else {
+                    continue;
+                };
+                let state = kinds.entry(kind).or_default();
+                for mut entity in entities {
+                    if !entity.is_object() {
+                        continue;
+                    }
+                    strip_operators(&mut entity);
+                    state.add(kind, entity);
+                }
+            }
+        }
+
+        let kinds = kinds
+            .into_iter()
+            .map(|(kind, state)| (kind, state.finish()))
+            .collect();
+        Self { kinds }
+    }
+}
+
+#[derive(Default)]
+struct KindState {
+    entities: usize,
+    model: Model,
+    keys: BTreeMap<String, KeyDrift>,
+}
+
+impl KindState {
+    fn add(&mut self, kind: EntityKind, entity: Value) {
+        self.entities += 1;
+        let mismatches = match kind {
+            EntityKind::Achievements => probe::<Achievements>(&mut self.model, entity.clone()),
+            EntityKind::Cultures => probe::<Cultures>(&mut self.model, entity.clone()),
+            EntityKind::Decks => probe::<Decks>(&mut self.model, entity.clone()),
+            EntityKind::Dicta => probe::<Dicta>(&mut self.model, entity.clone()),
+            EntityKind::Elements => probe::<Elements>(&mut self.model, entity.clone()),
+            EntityKind::Endings => probe::<Endings>(&mut self.model, entity.clone()),
+            EntityKind::Legacies => probe::<Legacies>(&mut self.model, entity.clone()),
+            EntityKind::Levers => probe::<Levers>(&mut self.model, entity.clone()),
+            EntityKind::Portals => probe::<Portals>(&mut self.model, entity.clone()),
+            EntityKind::Recipes => probe::<Recipes>(&mut self.model, entity.clone()),
+            EntityKind::Settings => probe::<Settings>(&mut self.model, entity.clone()),
+            EntityKind::Verbs => probe::<Verbs>(&mut self.model, entity.clone()),
+        };
+
+        if let Value::Object(entity) = &entity {
+            for (key, value) in entity {
+                self.observe(key.clone(), value);
+            }
+        }
+        for (path, message) in mismatches {
+            let key = self.keys.entry(path).or_default();
+            *key.mismatches.entry(message).or_default() += 1;
+        }
+    }
+
+    fn observe(&mut self, path: String, value: &Value) {
+        let key = self.keys.entry(path.clone()).or_default();
+        key.count += 1;
+        *key.types.entry(JsonType::of(value)).or_default() += 1;
+        let scalar = !matches!(value, Value::Array(_) | Value::Object(_));
+        if scalar && key.examples.len() < EXAMPLES && !key.examples.contains(value) {
+            key.examples.push(value.clone());
+        }
+
+        match value {
+            Value::Object(map) => {
+                let is_map = (self.model.nodes.get(&path))
+                    .is_some_and(|node| node.expected.contains(&Expected::Map));
+                for (key, value) in map {
+                    let key = if is_map { "*" } else { key };
+                    self.observe(join(&path, key), value);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.observe(format!("{path}[]"), item);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn finish(self) -> KindDrift {
+        let Self {
+            entities,
+            model,
+            mut keys,
+        } = self;
+        for (path, key) in &mut keys {
+            if let Some(node) = model.nodes.get(path) {
+                key.modelled = true;
+                key.expected = node.expected.clone();
+                key.nullable = node.nullable;
+            }
+        }
+
+        let mut unseen = Vec::new();
+        for (path, node) in &model.nodes {
+            for field in &node.fields {
+                let field = join(path, field);
+                let seen = |key: &String| key.eq_ignore_ascii_case(&field);
+                if !keys.keys().any(seen) && !unseen.iter().any(seen) {
+                    unseen.push(field);
+                }
+            }
+        }
+        KindDrift {
+            entities,
+            keys,
+            unseen,
+        }
+    }
+}
+
+/// Remove the merge operators from every key, dropping `$remove` keys.
+fn strip_operators(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            *map = mem::take(map)
+                .into_iter()
+                .filter_map(|(key, mut value)| {
+                    strip_operators(&mut value);
+                    match MergeOp::split_key(&key) {
+                        (_, Some(MergeOp::Remove)) => None,
+                        (name, Some(_)) => Some((name.to_owned(), value)),
+                        (_, None) => Some((key, value)),
+                    }
+                })
+                .collect();
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_operators),
+        _ => (),
+    }
+}
+
+/// What the model expects, by normalized key path.
+#[derive(Default)]
+struct Model {
+    nodes: BTreeMap<String, Node>,
+    /// Where the last deserialization failed.
+    failure: Option<Failure>,
+}
+
+#[derive(Default)]
+struct Node {
+    expected: BTreeSet<Expected>,
+    nullable: bool,
+    /// The fields (and aliases) if this is a struct.
+    fields: BTreeSet<&'static str>,
+}
+
+struct Failure {
+    path: String,
+    raw: Vec<PathSegment>,
+    /// The key that failed, [`None`] if the value did.
+    key: Option<String>,
+}
+
+impl Hook for RefCell<Model> {
+    fn expect(&self, at: At<'_>, expected: Expected) {
+        let mut model = self.borrow_mut();
+        let node = model.nodes.entry(at.normalized.to_owned()).or_default();
+        node.expected.insert(expected);
+    }
+
+    fn fields(&self, at: At<'_>, fields: &'static [&'static str]) {
+        let mut model = self.borrow_mut();
+        let node = model.nodes.entry(at.normalized.to_owned()).or_default();
+        node.fields.extend(fields);
+    }
+
+    fn nullable(&self, at: At<'_>) {
+        let mut model = self.borrow_mut();
+        model
+            .nodes
+            .entry(at.normalized.to_owned())
+            .or_default()
+            .nullable = true;
+    }
+
+    /// Only the innermost failure is kept, the others are where it propagated through.
+    fn failed(&self, at: At<'_>, key: Option<&str>) {
+        let mut model = self.borrow_mut();
+        if model.failure.is_none() {
+            model.failure = Some(Failure {
+                path: at.normalized.to_owned(),
+                raw: at.path.to_vec(),
+                key: key.map(ToOwned::to_owned),
+            });
+        }
+    }
+}
+
+/// Deserialize `value` as `T`, recording what `T` expects in `model`.
+///
+/// Values that don't fit are removed and deserialization is retried, until it either succeeds or
+/// fails on a missing field, so a single bad value doesn't hide the rest of the entity. Returns the
+/// normalized path and message of every value that didn't fit.
+fn probe<T: DeserializeOwned>(model: &mut Model, mut value: Value) -> Vec<(String, String)> {
+    let cell = RefCell::new(mem::take(model));
+    let mut mismatches = Vec::new();
+    loop {
+        cell.borrow_mut().failure = None;
+        let Err(err) = T::deserialize(Tracked::new(value.clone(), &cell)) else {
+            break;
+        };
+        let Some(Failure { path, mut raw, key }) = cell.borrow_mut().failure.take() else {
+            break;
+        };
+        let message = err.to_string();
+        if message.starts_with("missing field") {
+            break;
+        }
+        // Unknown keys are found by the plain walk, only report other failures
+        if key.is_none() || !message.starts_with("unknown field") {
+            mismatches.push((path, message));
+        }
+        if let Some(key) = key {
+            raw.push(PathSegment::Key(key));
+        }
+        if !remove_at(&mut value, &raw) {
+            break;
+        }
+    }
+    *model = cell.into_inner();
+    mismatches
+}
+
+fn remove_at(value: &mut Value, path: &[PathSegment]) -> bool {
+    let Some((last, path)) = path.split_last() else {
+        return false;
+    };
+    let mut value = value;
+    for segment in path {
+        let next = match segment {
+            PathSegment::Key(key) => value.as_object_mut().and_then(|map| map.get_mut(key)),
+            PathSegment::Index(index) => value.as_array_mut().and_then(|seq| seq.get_mut(*index)),
+        };
+        let Some(next) = next else {
+            return false;
+        };
+        value = next;
+    }
+    match (last, value) {
+        (PathSegment::Key(key), Value::Object(map)) => map.remove(key).is_some(),
+        (PathSegment::Index(index), Value::Array(seq)) if *index < seq.len() => {
+            seq.remove(*index);
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,498 @@
+//! The engine's expressions used as requirement and effect values.
+//!
+//! ```text
+//! expr      := product (('+' | '-') product)*
+//! product   := unary (('*' | '/' | '%') unary)*
+//! unary     := '-' unary | atom
+//! atom      := number | aspect | reference | '(' expr ')'
+//! reference := '[' (path ':')? aspect ']'
+//! ```
+//!
+//! A bare `aspect` is the same as `[aspect]`, the value of that aspect in the current context.
+//! A `path` like `~/exterior` selects another context, for example all cards in a sphere.
+
+use crate::data::StringOrI32;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i32),
+    /// The value of `aspect`, either in the current context or in the one at `path`.
+    Reference {
+        path: Option<String>,
+        aspect: String,
+    },
+    Neg(Box<Expr>),
+    Binary {
+        op: BinOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(input: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser { input, pos: 0 };
+        let expr = parser.expr()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(expr),
+            Some(c) => Err(parser.error(ParseErrorKind::Unexpected(c))),
+        }
+    }
+
+    /// Evaluate the expression, aspects are looked up in `context`.
+    pub fn eval(&self, context: &impl Context) -> Result<i32, EvalError> {
+        match self {
+            Expr::Number(n) => Ok(*n),
+            Expr::Reference { path, aspect } => {
+                context
+                    .aspect(path.as_deref(), aspect)
+                    .ok_or_else(|| EvalError::Unresolved {
+                        path: path.clone(),
+                        aspect: aspect.clone(),
+                    })
+            }
+            Expr::Neg(expr) => expr.eval(context)?.checked_neg().ok_or(EvalError::Overflow),
+            Expr::Binary { op, lhs, rhs } => {
+                let (lhs, rhs) = (lhs.eval(context)?, rhs.eval(context)?);
+                match op {
+                    BinOp::Add => lhs.checked_add(rhs).ok_or(EvalError::Overflow),
+                    BinOp::Sub => lhs.checked_sub(rhs).ok_or(EvalError::Overflow),
+                    BinOp::Mul => lhs.checked_mul(rhs).ok_or(EvalError::Overflow),
+                    BinOp::Div | BinOp::Rem if rhs == 0 => Err(EvalError::DivisionByZero),
+                    BinOp::Div => lhs.checked_div(rhs).ok_or(EvalError::Overflow),
+                    BinOp::Rem => lhs.checked_rem(rhs).ok_or(EvalError::Overflow),
+                }
+            }
+        }
+    }
+
+    /// Every aspect the expression refers to, together with its path.
+    pub fn references(&self) -> Vec<(Option<&str>, &str)> {
+        let mut out = Vec::new();
+        self.collect_references(&mut out);
+        out
+    }
+
+    fn collect_references<'a>(&'a self, out: &mut Vec<(Option<&'a str>, &'a str)>) {
+        match self {
+            Expr::Number(_) => (),
+            Expr::Reference { path, aspect } => out.push((path.as_deref(), aspect)),
+            Expr::Neg(expr) => expr.collect_references(out),
+            Expr::Binary { lhs, rhs, .. } => {
+                lhs.collect_references(out);
+                rhs.collect_references(out);
+            }
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{n}"),
+            Expr::Reference { path: None, aspect } => write!(f, "[{aspect}]"),
+            Expr::Reference {
+                path: Some(path),
+                aspect,
+            } => write!(f, "[{path} : {aspect}]"),
+            Expr::Neg(expr) => write!(f, "-{expr}"),
+            Expr::Binary { op, lhs, rhs } => write!(f, "({lhs} {} {rhs})", op.as_str()),
+        }
+    }
+}
+
+impl StringOrI32 {
+    /// Parse the value as an [`Expr`], a plain number is an [`Expr::Number`].
+    pub fn expr(&self) -> Result<Expr, ParseError> {
+        match self {
+            StringOrI32::Str(str) => Expr::parse(str),
+            StringOrI32::I32(i32) => Ok(Expr::Number(*i32)),
+        }
+    }
+}
+
+/// Where [`Expr::eval`] looks up aspect values.
+pub trait Context {
+    /// The value of `aspect` at `path` (or the current context), [`None`] if `path` can't be
+    /// resolved. Aspects that aren't present have the value `0`.
+    fn aspect(&self, path: Option<&str>, aspect: &str) -> Option<i32>;
+}
+
+/// The aspects of the current context, other paths can't be resolved.
+impl Context for BTreeMap<String, i32> {
+    fn aspect(&self, path: Option<&str>, aspect: &str) -> Option<i32> {
+        match path {
+            None => Some(self.get(aspect).copied().unwrap_or(0)),
+            Some(_) => None,
+        }
+    }
+}
+
+impl<F> Context for F
+where
+    F: Fn(Option<&str>, &str) -> Option<i32>,
+{
+    fn aspect(&self, path: Option<&str>, aspect: &str) -> Option<i32> {
+        self(path, aspect)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            input: self.input.to_owned(),
+            pos: self.pos,
+            kind,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.product()?;
+        loop {
+            self.skip_whitespace();
+            let op = match self.peek() {
+                Some('+') => BinOp::Add,
+                Some('-') => BinOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.bump();
+            let rhs = self.product()?;
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            let op = match self.peek() {
+                Some('*') => BinOp::Mul,
+                Some('/') => BinOp::Div,
+                Some('%') => BinOp::Rem,
+                _ => return Ok(lhs),
+            };
+            self.bump();
+            let rhs = self.unary()?;
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('-') => {
+                let start = self.pos;
+                self.bump();
+                self.skip_whitespace();
+                // Parsed with its sign, so `i32::MIN` is a valid literal
+                if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    return self.number(start, true);
+                }
+                match self.unary()? {
+                    Expr::Number(n) => Ok(n
+                        .checked_neg()
+                        .map_or_else(|| Expr::Neg(Box::new(Expr::Number(n))), Expr::Number)),
+                    expr => Ok(Expr::Neg(Box::new(expr))),
+                }
+            }
+            _ => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd)),
+            Some('(') => {
+                self.bump();
+                let expr = self.expr()?;
+                self.skip_whitespace();
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some('[') => {
+                self.bump();
+                self.reference()
+            }
+            Some(c) if c.is_ascii_digit() => self.number(self.pos, false),
+            Some(c) if is_id_char(c) => Ok(Expr::Reference {
+                path: None,
+                aspect: self.id().to_owned(),
+            }),
+            Some(c) => Err(self.error(ParseErrorKind::Unexpected(c))),
+        }
+    }
+
+    /// The digits at the current position, `start` is where the number (or its sign) starts.
+    fn number(&mut self, start: usize, negative: bool) -> Result<Expr, ParseError> {
+        let digits = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        let digits = &self.input[digits..self.pos];
+        match negative {
+            true => format!("-{digits}").parse(),
+            false => digits.parse(),
+        }
+        .map(Expr::Number)
+        .map_err(|_| ParseError {
+            input: self.input.to_owned(),
+            pos: start,
+            kind: ParseErrorKind::NumberTooLarge,
+        })
+    }
+
+    /// The rest of a `[path : aspect]` reference, after the `[`.
+    fn reference(&mut self) -> Result<Expr, ParseError> {
+        let start = self.pos;
+        let end = match self.input[start..].find(']') {
+            Some(len) => start + len,
+            None => {
+                self.pos = self.input.len();
+                return Err(self.error(ParseErrorKind::Expected(']')));
+            }
+        };
+        let inner = &self.input[start..end];
+        let (path, aspect) = match inner.rsplit_once(':') {
+            Some((path, aspect)) => (Some(path.trim().to_owned()), aspect.trim()),
+            None => (None, inner.trim()),
+        };
+        if aspect.is_empty() {
+            self.pos = end;
+            return Err(self.error(ParseErrorKind::ExpectedAspect));
+        }
+        self.pos = end + 1;
+        Ok(Expr::Reference {
+            path,
+            aspect: aspect.to_owned(),
+        })
+    }
+
+    fn id(&mut self) -> &str {
+        let start = self.pos;
+        while self.peek().is_some_and(is_id_char) {
+            self.bump();
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            _ => Err(self.error(ParseErrorKind::Expected(expected))),
+        }
+    }
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// `input` couldn't be parsed at byte offset `pos`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub input: String,
+    pub pos: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    Unexpected(char),
+    UnexpectedEnd,
+    Expected(char),
+    ExpectedAspect,
+    NumberTooLarge,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at {}: ", self.input, self.pos)?;
+        match &self.kind {
+            ParseErrorKind::Unexpected(c) => write!(f, "unexpected {c:?}"),
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ParseErrorKind::Expected(c) => write!(f, "expected {c:?}"),
+            ParseErrorKind::ExpectedAspect => write!(f, "expected an aspect"),
+            ParseErrorKind::NumberTooLarge => write!(f, "number too large"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// The context couldn't resolve `path`.
+    Unresolved {
+        path: Option<String>,
+        aspect: String,
+    },
+    DivisionByZero,
+    Overflow,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Unresolved {
+                path: Some(path),
+                aspect,
+            } => write!(f, "can't resolve [{path} : {aspect}]"),
+            EvalError::Unresolved { path: None, aspect } => write!(f, "can't resolve [{aspect}]"),
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(input: &str) -> Result<i32, EvalError> {
+        let context = BTreeMap::from([("edge".to_owned(), 3), ("heart".to_owned(), 4)]);
+        Expr::parse(input).unwrap().eval(&context)
+    }
+
+    fn parse_error(input: &str) -> (usize, ParseErrorKind) {
+        let err = Expr::parse(input).unwrap_err();
+        (err.pos, err.kind)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval("10 - 4 - 3"), Ok(3));
+        assert_eq!(eval("7 % 4 * 2"), Ok(6));
+        assert_eq!(eval("-2 * -3"), Ok(6));
+        assert_eq!(eval("--5"), Ok(5));
+        assert_eq!(
+            Expr::parse("1 + 2 * 3").unwrap().to_string(),
+            "(1 + (2 * 3))"
+        );
+    }
+
+    #[test]
+    fn references() {
+        assert_eq!(eval("edge * heart"), Ok(12));
+        assert_eq!(eval("[edge] + [ heart ]"), Ok(7));
+        assert_eq!(eval("moth"), Ok(0));
+        assert_eq!(
+            Expr::parse("[~/exterior : edge] - lantern")
+                .unwrap()
+                .references(),
+            [(Some("~/exterior"), "edge"), (None, "lantern")]
+        );
+        assert_eq!(
+            eval("[~/exterior : edge]"),
+            Err(EvalError::Unresolved {
+                path: Some("~/exterior".to_owned()),
+                aspect: "edge".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn min_literal() {
+        assert_eq!(Expr::parse("-2147483648"), Ok(Expr::Number(i32::MIN)));
+        assert_eq!(Expr::parse("- 2147483648"), Ok(Expr::Number(i32::MIN)));
+        assert_eq!(eval("-2147483648 + 1"), Ok(i32::MIN + 1));
+        assert_eq!(
+            parse_error("2147483648"),
+            (0, ParseErrorKind::NumberTooLarge)
+        );
+        assert_eq!(
+            parse_error("1 + -2147483649"),
+            (4, ParseErrorKind::NumberTooLarge)
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(eval("2147483647 + 1"), Err(EvalError::Overflow));
+        assert_eq!(eval("-2147483648 - 1"), Err(EvalError::Overflow));
+        assert_eq!(eval("65536 * 65536"), Err(EvalError::Overflow));
+        assert_eq!(eval("-(-2147483648)"), Err(EvalError::Overflow));
+        assert_eq!(eval("-2147483648 / -1"), Err(EvalError::Overflow));
+        assert_eq!(eval("1 / 0"), Err(EvalError::DivisionByZero));
+        assert_eq!(eval("1 % (edge - 3)"), Err(EvalError::DivisionByZero));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse_error(""), (0, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(parse_error("1 +"), (3, ParseErrorKind::UnexpectedEnd));
+        assert_eq!(parse_error("(1 + 2"), (6, ParseErrorKind::Expected(')')));
+        assert_eq!(parse_error("[edge"), (5, ParseErrorKind::Expected(']')));
+        assert_eq!(
+            parse_error("[~/exterior : ]"),
+            (14, ParseErrorKind::ExpectedAspect)
+        );
+        assert_eq!(parse_error("1 2"), (2, ParseErrorKind::Unexpected('2')));
+        assert_eq!(
+            parse_error("edge & heart"),
+            (5, ParseErrorKind::Unexpected('&'))
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! The graph of recipes that lead into each other through `linked`, `alt` and `inductions`.
+
+use crate::{
+    compendium::Compendium,
+    data::{Recipes, RecipesLinkedExpulsion, StringMapOrArray, StringOrStruct},
+    simulate::matches_verb,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{self, Write},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    Linked,
+    Alt,
+    Induction,
+}
+
+impl EdgeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EdgeKind::Linked => "linked",
+            EdgeKind::Alt => "alt",
+            EdgeKind::Induction => "induction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Edge<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub kind: EdgeKind,
+    /// The chance in percent, [`None`] if the recipe always follows.
+    pub chance: Option<u32>,
+    pub additional: bool,
+    pub expulsion: Option<&'a RecipesLinkedExpulsion>,
+    pub to_path: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Node<'a> {
+    pub label: Option<&'a str>,
+    pub action_id: Option<&'a str>,
+    /// Whether the recipe doesn't exist (or is a wildcard).
+    pub missing: bool,
+}
+
+/// Where to start building the graph from.
+#[derive(Debug, Clone)]
+pub enum Root {
+    Recipe(String),
+    /// Every craftable recipe of the verb.
+    Verb(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GraphFilter {
+    /// Only include recipes reachable from these, every recipe if empty.
+    pub roots: Vec<Root>,
+    /// Only follow this many edges from the roots.
+    ///
+    /// Recipes at the limit are still nodes of the graph, but none of their edges are.
+    pub max_depth: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecipeGraph<'a> {
+    pub nodes: BTreeMap<&'a str, Node<'a>>,
+    pub edges: Vec<Edge<'a>>,
+}
+
+fn edges_of(recipe: &Recipes) -> Vec<Edge<'_>> {
+    let from = recipe.id.as_str();
+    let mut edges = Vec::new();
+    let plain = |to, kind| Edge {
+        from,
+        to,
+        kind,
+        chance: None,
+        additional: false,
+        expulsion: None,
+        to_path: None,
+    };
+
+    if let Some(links) = &recipe.linked {
+        let links: Vec<_> = match links {
+            StringMapOrArray::Str(to) => {
+                edges.push(plain(to.as_str(), EdgeKind::Linked));
+                Vec::new()
+            }
+            StringMapOrArray::Map(link) => vec![link],
+            StringMapOrArray::Arr(links) => links.iter().collect(),
+        };
+        for link in links {
+            edges.push(match link {
+                StringOrStruct::Str(to) => plain(to, EdgeKind::Linked),
+                StringOrStruct::Struct(link) => Edge {
+                    from,
+                    to: &link.id,
+                    kind: EdgeKind::Linked,
+                    chance: link.chance,
+                    additional: link.additional,
+                    expulsion: link.expulsion.as_ref(),
+                    to_path: link.topath.as_deref(),
+                },
+            });
+        }
+    }
+    for alt in recipe.alt.iter().flatten() {
+        edges.push(Edge {
+            from,
+            to: &alt.id,
+            kind: EdgeKind::Alt,
+            chance: alt.chance,
+            additional: alt.additional,
+            expulsion: alt.expulsion.as_ref(),
+            to_path: None,
+        });
+    }
+    for induction in recipe.inductions.iter().flatten() {
+        edges.push(Edge {
+            chance: induction.chance,
+            ..plain(&induction.id, EdgeKind::Induction)
+        });
+    }
+    edges
+}
+
+impl<'a> RecipeGraph<'a> {
+    pub fn build(compendium: &'a Compendium, filter: &GraphFilter) -> Self {
+        let mut graph = RecipeGraph {
+            nodes: BTreeMap::new(),
+            edges: Vec::new(),
+        };
+
+        let mut queue: VecDeque<(&'a str, usize)> = VecDeque::new();
+        if filter.roots.is_empty() {
+            queue.extend(compendium.recipes.keys().map(|id| (id.as_str(), 0)));
+        }
+        for root in &filter.roots {
+            match root {
+                Root::Recipe(id) => {
+                    if let Some((id, _)) = compendium.recipes.get_key_value(id) {
+                        queue.push_back((id, 0));
+                    }
+                }
+                Root::Verb(verb) => queue.extend(
+                    compendium
+                        .recipes
+                        .iter()
+                        .filter(|(_, recipe)| {
+                            recipe.craftable == Some(true)
+                                && recipe
+                                    .action_id
+                                    .as_deref()
+                                    .is_some_and(|action_id| matches_verb(action_id, verb))
+                        })
+                        .map(|(id, _)| (id.as_str(), 0)),
+                ),
+            }
+        }
+
+        while let Some((id, depth)) = queue.pop_front() {
+            if graph.nodes.contains_key(id) {
+                continue;
+            }
+            let Some(recipe) = compendium.recipes.get(id) else {
+                graph.nodes.insert(
+                    id,
+                    Node {
+                        label: None,
+                        action_id: None,
+                        missing: true,
+                    },
+                );
+                continue;
+            };
+            graph.nodes.insert(
+                id,
+                Node {
+                    label: recipe.label.as_deref(),
+                    action_id: recipe.action_id.as_deref(),
+                    missing: false,
+                },
+            );
+            if filter.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                continue;
+            }
+            for edge in edges_of(recipe) {
+                queue.push_back((edge.to, depth + 1));
+                graph.edges.push(edge);
+            }
+        }
+        graph
+    }
+
+    /// The edges grouped by the recipe they start at.
+    pub fn adjacency(&self) -> BTreeMap<&'a str, Vec<&Edge<'a>>> {
+        let mut adjacency: BTreeMap<&str, Vec<&Edge>> =
+            self.nodes.keys().map(|id| (*id, Vec::new())).collect();
+        for edge in &self.edges {
+            adjacency.entry(edge.from).or_default().push(edge);
+        }
+        adjacency
+    }
+
+    /// Serialize as a JSON adjacency list, `{ "<recipe>": [<edge>...] }`.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.adjacency())
+    }
+
+    /// Render as a Graphviz DOT digraph.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph recipes {\n");
+        for (id, node) in &self.nodes {
+            let label = node.label.unwrap_or(id);
+            let style = match node.missing {
+                true => ", style=dashed",
+                false => "",
+            };
+            let _ = writeln!(
+                out,
+                "    {} [label={}{style}];",
+                dot_str(id),
+                dot_str(&format!("{id}\n{label}"))
+            );
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "    {} -> {} [label={}{}];",
+                dot_str(edge.from),
+                dot_str(edge.to),
+                dot_str(&edge_label(edge)),
+                match edge.kind {
+                    EdgeKind::Linked => "",
+                    EdgeKind::Alt => ", style=dashed",
+                    EdgeKind::Induction => ", style=dotted",
+                }
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as GraphML.
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+            "\n",
+            r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#,
+            "\n",
+            r#"  <key id="actionid" for="node" attr.name="actionid" attr.type="string"/>"#,
+            "\n",
+            r#"  <key id="missing" for="node" attr.name="missing" attr.type="boolean"/>"#,
+            "\n",
+            r#"  <key id="kind" for="edge" attr.name="kind" attr.type="string"/>"#,
+            "\n",
+            r#"  <key id="chance" for="edge" attr.name="chance" attr.type="int"/>"#,
+            "\n",
+            r#"  <key id="additional" for="edge" attr.name="additional" attr.type="boolean"/>"#,
+            "\n",
+            r#"  <key id="expulsion" for="edge" attr.name="expulsion" attr.type="string"/>"#,
+            "\n",
+            r#"  <key id="topath" for="edge" attr.name="topath" attr.type="string"/>"#,
+            "\n",
+            r#"  <graph id="recipes" edgedefault="directed">"#,
+            "\n",
+        ));
+        for (id, node) in &self.nodes {
+            let _ = writeln!(out, r#"    <node id="{}">"#, xml_escape(id));
+            if let Some(label) = node.label {
+                data(&mut out, "label", label);
+            }
+            if let Some(action_id) = node.action_id {
+                data(&mut out, "actionid", action_id);
+            }
+            data(&mut out, "missing", node.missing);
+            out.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                r#"    <edge source="{}" target="{}">"#,
+                xml_escape(edge.from),
+                xml_escape(edge.to)
+            );
+            data(&mut out, "kind", edge.kind.as_str());
+            if let Some(chance) = edge.chance {
+                data(&mut out, "chance", chance);
+            }
+            data(&mut out, "additional", edge.additional);
+            if let Some(expulsion) = edge.expulsion {
+                data(&mut out, "expulsion", expulsion_label(expulsion));
+            }
+            if let Some(to_path) = edge.to_path {
+                data(&mut out, "topath", to_path);
+            }
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+fn edge_label(edge: &Edge) -> String {
+    let mut label = edge.kind.as_str().to_owned();
+    if let Some(chance) = edge.chance {
+        let _ = write!(label, " {chance}%");
+    }
+    if edge.additional {
+        label.push_str(" +");
+    }
+    if let Some(expulsion) = edge.expulsion {
+        let _ = write!(label, "\nexpel {}", expulsion_label(expulsion));
+    }
+    if let Some(to_path) = edge.to_path {
+        let _ = write!(label, "\nto {to_path}");
+    }
+    label
+}
+
+fn expulsion_label(expulsion: &RecipesLinkedExpulsion) -> String {
+    let filter: Vec<&str> = expulsion.filter.keys().map(String::as_str).collect();
+    format!("{} x{}", filter.join(","), expulsion.limit)
+}
+
+fn dot_str(str: &str) -> String {
+    let mut out = String::with_capacity(str.len() + 2);
+    out.push('"');
+    for c in str.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn xml_escape(str: &str) -> String {
+    let mut out = String::with_capacity(str.len());
+    for c in str.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn data(out: &mut String, key: &str, value: impl fmt::Display) {
+    let _ = writeln!(
+        out,
+        r#"      <data key="{key}">{}</data>"#,
+        xml_escape(&value.to_string())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compendium::SourceFile, data::Data};
+    use serde_json::json;
+
+    #[test]
+    fn max_depth() {
+        let mut compendium = Compendium::new();
+        let data: Data = serde_json::from_value(json!({
+            "recipes": [
+                { "id": "a", "linked": [{ "id": "b" }] },
+                { "id": "b", "alt": [{ "id": "c" }] },
+                { "id": "c", "linked": [{ "id": "a" }, { "id": "gone" }] },
+            ],
+        }))
+        .unwrap();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "test.json".into(),
+        };
+        compendium.insert_data(&source, data);
+
+        let edges = |max_depth| {
+            let filter = GraphFilter {
+                roots: vec![Root::Recipe("a".to_owned())],
+                max_depth,
+            };
+            let graph = RecipeGraph::build(&compendium, &filter);
+            let nodes: Vec<_> = graph.nodes.keys().copied().collect();
+            let edges: Vec<_> = (graph.edges.iter())
+                .map(|edge| (edge.from, edge.to))
+                .collect();
+            (nodes, edges)
+        };
+
+        assert_eq!(edges(Some(0)), (vec!["a"], vec![]));
+        assert_eq!(edges(Some(1)), (vec!["a", "b"], vec![("a", "b")]));
+        let (nodes, all) = edges(None);
+        assert_eq!(nodes, ["a", "b", "c", "gone"]);
+        assert_eq!(all, [("a", "b"), ("b", "c"), ("c", "a"), ("c", "gone")]);
+        assert!(RecipeGraph::build(&compendium, &GraphFilter::default()).nodes["gone"].missing);
+    }
+}
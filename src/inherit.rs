@@ -0,0 +1,612 @@
+//! Resolution of `inherits` and `$derives` chains into effective entities.
+//!
+//! The engine supports two ways for an entity to build on another one:
+//!
+//! - `inherits` ([`Elements`], [`Recipes`]): every property the child does not set is taken from
+//!   the parent, every property the child does set replaces the parent's one.
+//! - `$derives` ([`Legacies`]): like `inherits`, but maps and lists the child sets are merged
+//!   with the parent's ones, where the child's map entries win and the parent's list items come
+//!   first.
+//!
+//! Since the deserialized entities can't tell an absent collection or string apart from an empty
+//! one, those are treated as "not set". Flags are [`Option`]s, so a child that explicitly sets one
+//! to `false` keeps it.
+
+use crate::{
+    compendium::{Compendium, SourceFile, Sourced},
+    data::{
+        Elements, Entity, EntityKind, Legacies, Recipes, RecipesInternalDeck, StringMapOrArray,
+        StringOrStruct,
+    },
+    span::{Location, Span},
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InheritMode {
+    /// Properties set by the child replace the parent's ones.
+    Replace,
+    /// Maps and lists set by the child are merged with the parent's ones.
+    Merge,
+}
+
+/// An entity that can build on a parent entity of the same kind.
+pub trait Inherit: Entity + Clone {
+    const MODE: InheritMode;
+
+    /// The `id` of the parent entity, if any.
+    fn parent(&self) -> Option<&str>;
+
+    /// Fill in (or merge) every property from `parent` according to [`Self::MODE`].
+    fn inherit_from(&mut self, parent: &Self);
+}
+
+/// A single property of an entity, see [`Inherit::inherit_from`].
+trait InheritField {
+    fn inherit_field(&mut self, parent: &Self, mode: InheritMode);
+}
+
+impl<T: InheritField + Clone> InheritField for Option<T> {
+    fn inherit_field(&mut self, parent: &Self, mode: InheritMode) {
+        match (self.as_mut(), parent) {
+            (_, None) => (),
+            (None, Some(parent)) => *self = Some(parent.clone()),
+            (Some(this), Some(parent)) => this.inherit_field(parent, mode),
+        }
+    }
+}
+
+impl<T: Clone> InheritField for Vec<T> {
+    fn inherit_field(&mut self, parent: &Self, mode: InheritMode) {
+        if self.is_empty() {
+            self.clone_from(parent);
+        } else if mode == InheritMode::Merge {
+            self.splice(0..0, parent.iter().cloned());
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> InheritField for BTreeMap<K, V> {
+    fn inherit_field(&mut self, parent: &Self, mode: InheritMode) {
+        if self.is_empty() {
+            self.clone_from(parent);
+        } else if mode == InheritMode::Merge {
+            for (key, value) in parent {
+                self.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+impl InheritField for String {
+    fn inherit_field(&mut self, parent: &Self, _: InheritMode) {
+        if self.is_empty() {
+            self.clone_from(parent);
+        }
+    }
+}
+
+/// Properties that are always replaced as a whole.
+macro_rules! impl_inherit_field_replace {
+    ($($ty:ty),* $(,)?) => {$(
+        impl InheritField for $ty {
+            fn inherit_field(&mut self, _: &Self, _: InheritMode) {}
+        }
+    )*};
+}
+
+impl_inherit_field_replace!(bool, u32, f32, RecipesInternalDeck);
+
+impl<T> InheritField for StringMapOrArray<T> {
+    fn inherit_field(&mut self, _: &Self, _: InheritMode) {}
+}
+
+impl<T> InheritField for StringOrStruct<T> {
+    fn inherit_field(&mut self, _: &Self, _: InheritMode) {}
+}
+
+/// Inherit every listed field of `$ty`, except the ones in `skip`.
+///
+/// The whole struct is destructured, so a field that is added later has to go in one of the lists.
+macro_rules! inherit_fields {
+    (
+        $this:ident, $parent:ident, $ty:ident {
+            $($field:ident),* $(,)?
+        }
+        skip { $($skip:ident),* $(,)? }
+    ) => {
+        let $ty { $($field,)* $($skip: _,)* } = $this;
+        $(InheritField::inherit_field($field, &$parent.$field, Self::MODE);)*
+    };
+}
+
+impl Inherit for Elements {
+    const MODE: InheritMode = InheritMode::Replace;
+
+    fn parent(&self) -> Option<&str> {
+        self.inherits.as_deref()
+    }
+
+    fn inherit_from(&mut self, parent: &Self) {
+        inherit_fields!(
+            self,
+            parent,
+            Elements {
+                achievements,
+                alpha_label_override,
+                ambits,
+                aspects,
+                audio,
+                burn_to,
+                comments,
+                commute,
+                decay_to,
+                description,
+                icon,
+                induces,
+                imms,
+                is_aspect,
+                is_hidden,
+                label,
+                lever,
+                lifetime,
+                manifestation_type,
+                metafictional,
+                no_art_needed,
+                resaturate,
+                reverse_ambit_tables_display,
+                slots,
+                sort,
+                unique,
+                uniqueness_group,
+                verb_icon,
+                xexts,
+                xtriggers,
+            }
+            skip { id, inherits }
+        );
+    }
+}
+
+impl Inherit for Recipes {
+    const MODE: InheritMode = InheritMode::Replace;
+
+    fn parent(&self) -> Option<&str> {
+        self.inherits.as_deref()
+    }
+
+    fn inherit_from(&mut self, parent: &Self) {
+        inherit_fields!(
+            self,
+            parent,
+            Recipes {
+                achievements,
+                action_id,
+                alt,
+                ambit_table,
+                aspects,
+                audio_oneshot,
+                blocks,
+                burnimage,
+                comments,
+                craftable,
+                deck_effects,
+                deleteverb,
+                description,
+                effects,
+                ending,
+                extant_reqs,
+                fx,
+                fx_reqs,
+                g_req,
+                haltverb,
+                hint_only,
+                icon,
+                inductions,
+                internal_deck,
+                label,
+                l_alt,
+                linked,
+                max_executions,
+                mutations,
+                ng_req,
+                notable,
+                portal_effect,
+                preface,
+                preslots,
+                purge,
+                requirements,
+                run,
+                signal_ending_flavour,
+                signal_important_loop,
+                slots,
+                start_description,
+                start_label,
+                table_reqs,
+                warmup,
+                xpans,
+            }
+            skip { id, inherits }
+        );
+    }
+}
+
+impl Inherit for Legacies {
+    const MODE: InheritMode = InheritMode::Merge;
+
+    fn parent(&self) -> Option<&str> {
+        self.derives.as_deref()
+    }
+
+    fn inherit_from(&mut self, parent: &Self) {
+        inherit_fields!(
+            self,
+            parent,
+            Legacies {
+                available_without_ending_match,
+                comments,
+                description,
+                effects,
+                excludes_on_ending,
+                family,
+                from_ending,
+                image,
+                label,
+                new_start,
+                start_description,
+                starting_verb_id,
+                startup,
+                statusbar_elements,
+                table_cover_image,
+            }
+            skip { derives, id }
+        );
+    }
+}
+
+/// Replace every [`Elements`], [`Recipes`] and [`Legacies`] of `compendium` with its effective
+/// version.
+///
+/// Entities that are part of a cycle are left as they are, and so is every entity that inherits
+/// from one of them (directly or not), which is reported as [`InheritError::UnresolvedParent`].
+/// Entities with a missing parent are left as they are, but can still be inherited from.
+pub fn resolve_inheritance(compendium: &mut Compendium) -> Vec<InheritError> {
+    let mut errors = Vec::new();
+    resolve_all(&mut compendium.elements, &mut errors);
+    resolve_all(&mut compendium.recipes, &mut errors);
+    resolve_all(&mut compendium.legacies, &mut errors);
+    errors
+}
+
+fn resolve_all<T: Inherit>(
+    entities: &mut BTreeMap<String, Sourced<T>>,
+    errors: &mut Vec<InheritError>,
+) {
+    let ids: Vec<String> = entities.keys().cloned().collect();
+    let mut resolved = HashMap::with_capacity(ids.len());
+    let mut stack = Vec::new();
+    let mut cycles = HashSet::new();
+    for id in &ids {
+        resolve_one(entities, id, &mut resolved, &mut stack, &mut cycles, errors);
+    }
+}
+
+/// Resolve the entity `id`, returns whether it can be inherited from.
+fn resolve_one<T: Inherit>(
+    entities: &mut BTreeMap<String, Sourced<T>>,
+    id: &str,
+    resolved: &mut HashMap<String, bool>,
+    stack: &mut Vec<String>,
+    cycles: &mut HashSet<String>,
+    errors: &mut Vec<InheritError>,
+) -> bool {
+    if let Some(&ok) = resolved.get(id) {
+        return ok;
+    }
+
+    if let Some(pos) = stack.iter().position(|entry| entry == id) {
+        let mut ids = stack[pos..].to_vec();
+        cycles.extend(ids.iter().cloned());
+        ids.push(id.to_owned());
+        errors.push(InheritError::Cycle {
+            kind: T::KIND,
+            ids,
+            source: entities[id].source.clone(),
+            span: entities[id].span,
+        });
+        return false;
+    }
+
+    let Some(parent) = entities[id].parent().map(ToOwned::to_owned) else {
+        resolved.insert(id.to_owned(), true);
+        return true;
+    };
+
+    if !entities.contains_key(&parent) {
+        errors.push(InheritError::MissingParent {
+            kind: T::KIND,
+            id: id.to_owned(),
+            parent,
+            source: entities[id].source.clone(),
+            span: entities[id].span,
+        });
+        resolved.insert(id.to_owned(), true);
+        return true;
+    }
+
+    stack.push(id.to_owned());
+    let ok = resolve_one(entities, &parent, resolved, stack, cycles, errors);
+    stack.pop();
+
+    if ok {
+        let parent = entities[&parent].value.clone();
+        if let Some(entity) = entities.get_mut(id) {
+            entity.inherit_from(&parent);
+        }
+    } else if !cycles.contains(id) {
+        errors.push(InheritError::UnresolvedParent {
+            kind: T::KIND,
+            id: id.to_owned(),
+            parent,
+            source: entities[id].source.clone(),
+            span: entities[id].span,
+        });
+    }
+    resolved.insert(id.to_owned(), ok);
+    ok
+}
+
+#[derive(Debug)]
+pub enum InheritError {
+    /// The parent of `id` does not exist.
+    MissingParent {
+        kind: EntityKind,
+        id: String,
+        parent: String,
+        /// The file `id` was defined in.
+        source: SourceFile,
+        span: Option<Span>,
+    },
+    /// The parent of `id` isn't resolved, because it is part of (or inherits from) a cycle.
+    UnresolvedParent {
+        kind: EntityKind,
+        id: String,
+        parent: String,
+        /// The file `id` was defined in.
+        source: SourceFile,
+        span: Option<Span>,
+    },
+    /// The entities in `ids` inherit from each other in a cycle, the first and last id are the
+    /// same.
+    Cycle {
+        kind: EntityKind,
+        ids: Vec<String>,
+        /// The file the first entity of the cycle was defined in.
+        source: SourceFile,
+        span: Option<Span>,
+    },
+}
+
+impl Display for InheritError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InheritError::MissingParent {
+                kind,
+                id,
+                parent,
+                source,
+                span,
+            } => write!(
+                f,
+                "{}: {kind}[{id}] inherits missing {parent:?}",
+                Location(source, *span)
+            ),
+            InheritError::UnresolvedParent {
+                kind,
+                id,
+                parent,
+                source,
+                span,
+            } => write!(
+                f,
+                "{}: {kind}[{id}] inherits {parent:?}, which is unresolved because of a cycle",
+                Location(source, *span)
+            ),
+            InheritError::Cycle {
+                kind,
+                ids,
+                source,
+                span,
+            } => write!(
+                f,
+                "{}: {kind} inherit cycle {}",
+                Location(source, *span),
+                ids.join(" -> ")
+            ),
+        }
+    }
+}
+
+impl Error for InheritError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Data;
+    use serde_json::json;
+
+    fn element(value: serde_json::Value) -> Elements {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn compendium(path: &str, data: serde_json::Value) -> Compendium {
+        let mut compendium = Compendium::new();
+        let source = SourceFile {
+            root: "root".into(),
+            path: path.into(),
+        };
+        compendium.insert_data(&source, serde_json::from_value::<Data>(data).unwrap());
+        compendium
+    }
+
+    #[test]
+    fn explicit_false_flag_wins_over_parent() {
+        let parent = element(json!({ "id": "parent", "isHidden": true, "unique": true }));
+        let mut child = element(json!({ "id": "child", "inherits": "parent", "isHidden": false }));
+        child.inherit_from(&parent);
+        assert_eq!(child.is_hidden, Some(false));
+        assert_eq!(child.unique, Some(true));
+    }
+
+    #[test]
+    fn unset_blocks_is_inherited() {
+        let recipe = |value| serde_json::from_value::<Recipes>(value).unwrap();
+        let parent = recipe(json!({ "id": "parent", "blocks": false }));
+        let mut unset = recipe(json!({ "id": "unset", "inherits": "parent" }));
+        let mut set = recipe(json!({ "id": "set", "inherits": "parent", "blocks": true }));
+        unset.inherit_from(&parent);
+        set.inherit_from(&parent);
+        assert_eq!(unset.blocks, Some(false));
+        assert_eq!(set.blocks, Some(true));
+    }
+
+    #[test]
+    fn cycle() {
+        let mut compendium = compendium(
+            "cycle.json",
+            json!({
+                "elements": [
+                    { "id": "a", "inherits": "b", "label": "A" },
+                    { "id": "b", "inherits": "a" },
+                    { "id": "child", "inherits": "b" },
+                    { "id": "grandchild", "inherits": "child" },
+                ],
+            }),
+        );
+        let errors = resolve_inheritance(&mut compendium);
+        let errors: Vec<_> = (errors.iter())
+            .map(|err| match err {
+                InheritError::Cycle { ids, .. } => format!("cycle {}", ids.join(" -> ")),
+                InheritError::UnresolvedParent { id, parent, .. } => {
+                    format!("unresolved {id} -> {parent}")
+                }
+                InheritError::MissingParent { id, .. } => format!("missing {id}"),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "cycle a -> b -> a",
+                "unresolved child -> b",
+                "unresolved grandchild -> child",
+            ]
+        );
+        // Nothing in or below the cycle inherits anything
+        assert_eq!(compendium.elements["b"].label, None);
+        assert_eq!(compendium.elements["grandchild"].label, None);
+    }
+
+    #[test]
+    fn missing_parent() {
+        let mut compendium = compendium(
+            "missing.json",
+            json!({
+                "elements": [
+                    { "id": "child", "inherits": "gone", "label": "Child" },
+                    { "id": "grandchild", "inherits": "child" },
+                ],
+            }),
+        );
+        let errors = resolve_inheritance(&mut compendium);
+        let [InheritError::MissingParent {
+            kind,
+            id,
+            parent,
+            source,
+            ..
+        }] = &errors[..]
+        else {
+            panic!("{errors:?}");
+        };
+        assert_eq!(*kind, EntityKind::Elements);
+        assert_eq!((id.as_str(), parent.as_str()), ("child", "gone"));
+        assert_eq!(source.path, std::path::Path::new("missing.json"));
+        // The child can still be inherited from
+        assert_eq!(
+            compendium.elements["grandchild"].label.as_deref(),
+            Some("Child")
+        );
+    }
+
+    #[test]
+    fn chain() {
+        let mut compendium = compendium(
+            "chain.json",
+            json!({
+                "elements": [
+                    { "id": "grandchild", "inherits": "child", "label": "Grandchild" },
+                    {
+                        "id": "child",
+                        "inherits": "parent",
+                        "aspects": { "edge": 2 },
+                        "description": "Child",
+                    },
+                    {
+                        "id": "parent",
+                        "aspects": { "heart": 1 },
+                        "description": "Parent",
+                        "icon": "parent",
+                    },
+                ],
+            }),
+        );
+        assert!(resolve_inheritance(&mut compendium).is_empty());
+        let grandchild = &compendium.elements["grandchild"].value;
+        assert_eq!(grandchild.label.as_deref(), Some("Grandchild"));
+        assert_eq!(grandchild.description.as_deref(), Some("Child"));
+        assert_eq!(grandchild.icon.as_deref(), Some("parent"));
+        // `inherits` replaces the aspects as a whole
+        assert_eq!(grandchild.aspects, BTreeMap::from([("edge".to_owned(), 2)]));
+    }
+
+    #[test]
+    fn derives_merges() {
+        let mut compendium = compendium(
+            "legacies.json",
+            json!({
+                "legacies": [
+                    {
+                        "id": "parent",
+                        "desc": "Parent",
+                        "fromending": "",
+                        "effects": { "a": 1, "b": 1 },
+                        "excludesOnEnding": ["x"],
+                        "label": "Parent",
+                    },
+                    {
+                        "id": "child",
+                        "$derives": "parent",
+                        "desc": "",
+                        "fromending": "",
+                        "effects": { "b": 2, "c": 2 },
+                        "excludesOnEnding": ["y"],
+                    },
+                ],
+            }),
+        );
+        assert!(resolve_inheritance(&mut compendium).is_empty());
+        let child = &compendium.legacies["child"].value;
+        assert_eq!(child.description, "Parent");
+        assert_eq!(child.label.as_deref(), Some("Parent"));
+        let effects = serde_json::to_value(&child.effects).unwrap();
+        assert_eq!(effects, json!({ "a": 1, "b": 2, "c": 2 }));
+        assert_eq!(
+            child.excludes_on_ending.as_deref(),
+            Some(&["x".to_owned(), "y".to_owned()][..])
+        );
+    }
+}
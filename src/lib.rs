@@ -1,3 +1,28 @@
+pub mod compendium;
 pub mod config;
 pub mod data;
+pub mod deck;
+pub mod diagnostic;
+pub mod diff;
+pub mod discover;
+pub mod drift;
+pub mod expr;
+pub mod graph;
+pub mod inherit;
+pub mod loader;
+pub mod merge;
+pub mod plan;
 pub mod reader;
+pub mod refs;
+pub mod simulate;
+pub mod slots;
+pub mod span;
+pub mod strict;
+#[cfg(test)]
+mod temp_dir;
+pub mod track;
+pub mod unknown;
+pub mod usage;
+pub mod validate;
+pub mod writer;
+pub mod xtrigger;
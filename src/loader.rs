@@ -0,0 +1,330 @@
+use crate::{
+    compendium::{Compendium, SourceFile},
+    config::{strip_root, ResolvedConfig, ResolvedSourceFormat, SourceEntry, SourceFileError},
+    data::EntityKind,
+    merge::{EntityError, MergeError, RawCompendium, RawData},
+    reader::Reader,
+};
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+/// Reads every source file of a [`ResolvedConfig`].
+#[derive(Default)]
+pub struct Loader {
+    reader: Reader,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load all sources into one [`Compendium`], stopping at the first file that fails.
+    ///
+    /// Entities are merged in source order, see [`crate::merge`]. Keys that aren't part of the data
+    /// model are handled as [`ResolvedConfig::unknown_fields`] says.
+    pub fn load(&mut self, config: &ResolvedConfig) -> Result<Compendium, LoadError> {
+        let mut raw = RawCompendium::new();
+        let files = self.read_files_with(config, Reader::deserialize_with_spans::<RawData>);
+        for file in files {
+            let (source, (data, spans)) = file?;
+            if let Err(err) = raw.insert_data(&source, data, &spans) {
+                return Err(LoadError::new(source, LoadErrorKind::Merge(err)));
+            }
+        }
+        raw.into_compendium(config.unknown_fields)
+            .map_err(LoadError::from)
+    }
+
+    /// Load all sources into one [`Compendium`] like [`Self::load`], but keep going past failures.
+    ///
+    /// Files that fail to read are skipped, as are entities inside a file that aren't objects,
+    /// can't be merged, or don't match the data model. Returns everything that did load together
+    /// with every error, in source order, followed by the entities that don't match the data model
+    /// (which are only checked once every source is merged).
+    pub fn load_partial(&mut self, config: &ResolvedConfig) -> (Compendium, Vec<LoadError>) {
+        let mut raw = RawCompendium::new();
+        let mut errors = Vec::new();
+        let files =
+            self.read_files_with(config, Reader::deserialize_with_spans::<Map<String, Value>>);
+        for file in files {
+            let (source, (data, spans)) = match file {
+                Ok(file) => file,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            let error = |message| LoadError::new(source.clone(), LoadErrorKind::Parse(message));
+
+            for (key, entities) in data {
+                let Ok(kind) = key.parse::<EntityKind>() else {
+                    errors.push(error(anyhow!("unknown entity kind `{key}`")));
+                    continue;
+                };
+                let Value::Array(entities) = entities else {
+                    errors.push(error(anyhow!("`{key}` is not an array")));
+                    continue;
+                };
+                let spans = spans.get(&kind).map(Vec::as_slice).unwrap_or_default();
+                for (i, entity) in entities.into_iter().enumerate() {
+                    let Value::Object(entity) = entity else {
+                        errors.push(error(anyhow!("`{key}[{i}]` is not an object")));
+                        continue;
+                    };
+                    if let Err(err) =
+                        raw.insert_entity(&source, kind, entity, spans.get(i).copied())
+                    {
+                        errors.push(LoadError::new(source.clone(), LoadErrorKind::Merge(err)));
+                    }
+                }
+            }
+        }
+
+        let (compendium, entity_errors) = raw.into_compendium_partial(config.unknown_fields);
+        errors.extend(
+            entity_errors
+                .into_iter()
+                .map(|err| LoadError::from(Box::new(err))),
+        );
+        (compendium, errors)
+    }
+
+    /// Deserialize every source file as `T`, in source order.
+    pub fn read_files<'a, T: DeserializeOwned + 'a>(
+        &'a mut self,
+        config: &'a ResolvedConfig,
+    ) -> impl Iterator<Item = Result<(SourceFile, T), LoadError>> + 'a {
+        self.read_files_with(config, Reader::deserialize_from)
+    }
+
+    /// Read every source file with `read`, in source order.
+    ///
+    /// A file that more than one source reaches (like a DLC folder that is linked into the
+    /// content folder and is a source of its own) is only read for the first of them, by its
+    /// [`SourceEntry::canonical`] path.
+    fn read_files_with<'a, T: 'a>(
+        &'a mut self,
+        config: &'a ResolvedConfig,
+        mut read: impl FnMut(&mut Reader, &Path, ResolvedSourceFormat) -> anyhow::Result<T> + 'a,
+    ) -> impl Iterator<Item = Result<(SourceFile, T), LoadError>> + 'a {
+        let reader = &mut self.reader;
+        let mut seen = HashSet::new();
+        config
+            .source
+            .iter()
+            .flat_map(|source| source.source_files().map(move |file| (source, file)))
+            .filter(move |(_, file)| match file {
+                Ok(entry) => seen.insert(entry.canonical.clone()),
+                Err(_) => true,
+            })
+            .map(move |(source, file)| match file {
+                Ok(SourceEntry { entry, format, .. }) => {
+                    let file = SourceFile {
+                        root: source.root.clone(),
+                        path: strip_root(&source.root, &entry).to_owned(),
+                    };
+                    match read(reader, entry.path(), format) {
+                        Ok(t) => Ok((file, t)),
+                        Err(err) => Err(LoadError::new(file, LoadErrorKind::Parse(err))),
+                    }
+                }
+                Err(err) => Err(LoadError {
+                    root: source.root.clone(),
+                    path: err
+                        .path()
+                        .map(|path| path.strip_prefix(&source.root).unwrap_or(path).to_owned()),
+                    kind: LoadErrorKind::Walk(err),
+                }),
+            })
+    }
+}
+
+/// An error for a single file (or directory) of a source.
+#[derive(Debug)]
+pub struct LoadError {
+    pub root: PathBuf,
+    /// The path relative to [`Self::root`], if the error can be attributed to one.
+    pub path: Option<PathBuf>,
+    pub kind: LoadErrorKind,
+}
+
+impl LoadError {
+    pub fn new(source: SourceFile, kind: LoadErrorKind) -> Self {
+        Self {
+            root: source.root,
+            path: Some(source.path),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    /// Walking the source directory failed.
+    Walk(SourceFileError),
+    /// Reading or deserializing the file failed.
+    Parse(anyhow::Error),
+    /// Merging an entity of the file into the earlier sources failed.
+    Merge(MergeError),
+    /// An entity that was (last) defined in the file doesn't match the data model.
+    Entity(Box<EntityError>),
+}
+
+impl From<Box<EntityError>> for LoadError {
+    fn from(err: Box<EntityError>) -> Self {
+        Self::new(err.source.clone(), LoadErrorKind::Entity(err))
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}", self.root.join(path).display())?,
+            None => write!(f, "{}", self.root.display())?,
+        }
+        match &self.kind {
+            LoadErrorKind::Walk(err) => write!(f, ": {err}"),
+            LoadErrorKind::Parse(err) => write!(f, ": {err}"),
+            LoadErrorKind::Merge(err) => write!(f, ": {err}"),
+            LoadErrorKind::Entity(err) => write!(f, ": {err}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Walk(err) => Some(err),
+            LoadErrorKind::Parse(err) => Some(err.as_ref()),
+            LoadErrorKind::Merge(err) => Some(err),
+            LoadErrorKind::Entity(err) => Some(err.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, temp_dir::TempDir};
+    use std::fs;
+
+    #[test]
+    fn partial_load_keeps_going() {
+        let dir = TempDir::new("partial");
+        let files = [
+            ("one/elements/broken.json", r#"{ "elements": [{ "id": "#),
+            (
+                "two/elements/mixed.json",
+                r#"{
+                    "elements": [{ "id": "kept" }, 3, { "id": "unknown", "unknown": 1 }],
+                    "recipes": { "id": "single" },
+                    "nonsense": [],
+                    "verbs": [{ "label": "no id" }]
+                }"#,
+            ),
+            (
+                "three/elements/more.json",
+                r#"{ "elements": [{ "id": "also kept" }] }"#,
+            ),
+        ];
+        for (path, text) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        let config: Config = toml::from_str(&format!(
+            "[[source]]\nroot = {:?}\n[[source]]\nroot = {:?}\n[[source]]\nroot = {:?}\n",
+            dir.join("one"),
+            dir.join("two"),
+            dir.join("three")
+        ))
+        .unwrap();
+        let config = config.resolve().unwrap();
+
+        let (compendium, errors) = Loader::new().load_partial(&config);
+        assert_eq!(
+            compendium.elements.keys().collect::<Vec<_>>(),
+            ["also kept", "kept"]
+        );
+        let prefix = format!("{}/", config.source[0].root.parent().unwrap().display());
+        let errors: Vec<_> = (errors.iter())
+            .map(|err| err.to_string().replacen(&prefix, "", 1))
+            .collect();
+        // The errors of the files come in source order, the entities are only checked once
+        // everything is merged
+        assert_eq!(
+            errors[..5],
+            [
+                "one/elements/broken.json: EOF while parsing a value at line 1 column 23",
+                "two/elements/mixed.json: `elements[1]` is not an object",
+                "two/elements/mixed.json: `recipes` is not an array",
+                "two/elements/mixed.json: unknown entity kind `nonsense`",
+                "two/elements/mixed.json: verbs entity without an id",
+            ]
+        );
+        assert_eq!(errors.len(), 6);
+        assert!(errors[5]
+            .starts_with("two/elements/mixed.json: elements[unknown]: unknown field `unknown`"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_read_once() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new("loader");
+        let (content, dlc) = (dir.join("content"), dir.join("dlc"));
+        fs::create_dir_all(content.join("core/elements")).unwrap();
+        fs::create_dir_all(dlc.join("elements")).unwrap();
+        let element = |id: &str| format!(r#"{{ "elements": [{{ "id": "{id}" }}] }}"#);
+        fs::write(content.join("core/elements/tools.json"), element("hammer")).unwrap();
+        fs::write(dlc.join("elements/dlc.json"), element("saw")).unwrap();
+        // A duplicate link to a file, a loop and the DLC linked into the content
+        symlink("tools.json", content.join("core/elements/copy.json")).unwrap();
+        symlink("..", content.join("core/loop")).unwrap();
+        symlink(&dlc, content.join("dlc")).unwrap();
+
+        let config: Config = toml::from_str(&format!(
+            "[[source]]\nroot = {:?}\nfollow_links = true\n[[source]]\nroot = {:?}\n",
+            content, dlc
+        ))
+        .unwrap();
+        let config = config.resolve().unwrap();
+
+        let mut loader = Loader::new();
+        let mut read = Vec::new();
+        let mut errors = Vec::new();
+        for file in loader.read_files::<Value>(&config) {
+            match file {
+                Ok((source, _)) => read.push(source.path),
+                Err(err) => errors.push(err),
+            }
+        }
+        read.sort();
+        // Which of the two links to the tools is read depends on the order of the directory
+        assert_eq!(read.len(), 2, "{read:?}");
+        assert!(read[0].starts_with("core/elements"));
+        assert_eq!(read[1], Path::new("dlc/elements/dlc.json"));
+        assert!(matches!(
+            errors.as_slice(),
+            [LoadError {
+                kind: LoadErrorKind::Walk(SourceFileError::Loop { link, .. }),
+                ..
+            }] if link.ends_with("core/loop")
+        ));
+
+        let (compendium, _) = loader.load_partial(&config);
+        assert_eq!(
+            compendium.elements.keys().collect::<Vec<_>>(),
+            ["hammer", "saw"]
+        );
+    }
+}
@@ -0,0 +1,634 @@
+//! The engine's mod merge operators.
+//!
+//! An entity whose `id` was already defined by an earlier source replaces it, unless one of its
+//! keys uses a merge operator, in which case it patches the earlier definition instead:
+//!
+//! - `"key$add"`: append items to a list, or insert entries into a map.
+//! - `"key$remove"`: remove items from a list, or keys from a map.
+//! - `"key$prefix"` / `"key$postfix"`: prepend / append to a string.
+//! - `"key$extend"`: merge a map into the existing one, its keys can use operators again.
+//!
+//! Keys are matched case insensitively, like the engine does.
+
+use crate::{
+    compendium::{Compendium, SourceFile, Sourced},
+    data::{Entity, EntityKind},
+    span::{Span, Spans},
+    unknown::{from_value_lenient, Extra, UnknownFields},
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+    mem,
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeOp {
+    Add,
+    Remove,
+    Prefix,
+    Postfix,
+    Extend,
+}
+
+impl MergeOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MergeOp::Add => "$add",
+            MergeOp::Remove => "$remove",
+            MergeOp::Prefix => "$prefix",
+            MergeOp::Postfix => "$postfix",
+            MergeOp::Extend => "$extend",
+        }
+    }
+
+    /// Split `"key$op"` into `key` and its operator.
+    ///
+    /// Keys that only consist of an operator-like name (like `$derives`) are not operators.
+    pub fn split_key(key: &str) -> (&str, Option<MergeOp>) {
+        match key.rfind('$') {
+            Some(pos @ 1..) => match key[pos..].parse() {
+                Ok(op) => (&key[..pos], Some(op)),
+                Err(()) => (key, None),
+            },
+            _ => (key, None),
+        }
+    }
+}
+
+impl FromStr for MergeOp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "$add" => Ok(MergeOp::Add),
+            "$remove" => Ok(MergeOp::Remove),
+            "$prefix" => Ok(MergeOp::Prefix),
+            "$postfix" => Ok(MergeOp::Postfix),
+            "$extend" => Ok(MergeOp::Extend),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for MergeOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether any key of `entity` uses a merge operator.
+pub fn has_operators(entity: &Map<String, Value>) -> bool {
+    entity.keys().any(|key| MergeOp::split_key(key).1.is_some())
+}
+
+/// Apply `patch` to `base`, keys without an operator replace the existing value.
+pub fn apply(base: &mut Map<String, Value>, patch: Map<String, Value>) -> Result<(), MergeError> {
+    for (key, value) in patch {
+        let (name, op) = MergeOp::split_key(&key);
+        let name = find_key(base, name).unwrap_or_else(|| name.to_owned());
+        match op {
+            None => {
+                base.insert(name, value);
+            }
+            Some(op) => {
+                apply_op(base, &name, op, value).map_err(|reason| MergeError {
+                    key: key.clone(),
+                    op: Some(op),
+                    reason,
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn find_key(map: &Map<String, Value>, key: &str) -> Option<String> {
+    match map.contains_key(key) {
+        true => Some(key.to_owned()),
+        false => map.keys().find(|k| k.eq_ignore_ascii_case(key)).cloned(),
+    }
+}
+
+fn apply_op(
+    base: &mut Map<String, Value>,
+    key: &str,
+    op: MergeOp,
+    value: Value,
+) -> Result<(), MergeErrorReason> {
+    let Some(target) = base.get_mut(key) else {
+        match op {
+            MergeOp::Remove => (),
+            MergeOp::Extend => {
+                let mut map = Map::new();
+                match value {
+                    Value::Object(patch) => apply(&mut map, patch).map_err(Box::new)?,
+                    value => return Err(MergeErrorReason::Operand(value_type(&value))),
+                }
+                base.insert(key.to_owned(), Value::Object(map));
+            }
+            MergeOp::Add | MergeOp::Prefix | MergeOp::Postfix => {
+                base.insert(key.to_owned(), value);
+            }
+        }
+        return Ok(());
+    };
+
+    match (op, target, value) {
+        (MergeOp::Add, Value::Array(list), Value::Array(items)) => list.extend(items),
+        (MergeOp::Add, Value::Array(list), item) => list.push(item),
+        (MergeOp::Add, Value::Object(map), Value::Object(entries)) => map.extend(entries),
+        (MergeOp::Remove, Value::Array(list), Value::Array(items)) => {
+            list.retain(|item| !items.contains(item))
+        }
+        (MergeOp::Remove, Value::Array(list), item) => list.retain(|i| *i != item),
+        (MergeOp::Remove, Value::Object(map), Value::Array(keys)) => {
+            for key in keys {
+                match key {
+                    Value::String(key) => {
+                        if let Some(key) = find_key(map, &key) {
+                            map.remove(&key);
+                        }
+                    }
+                    key => return Err(MergeErrorReason::Operand(value_type(&key))),
+                }
+            }
+        }
+        (MergeOp::Remove, Value::Object(map), Value::String(key)) => {
+            if let Some(key) = find_key(map, &key) {
+                map.remove(&key);
+            }
+        }
+        (MergeOp::Prefix, Value::String(str), Value::String(prefix)) => str.insert_str(0, &prefix),
+        (MergeOp::Postfix, Value::String(str), Value::String(postfix)) => str.push_str(&postfix),
+        (MergeOp::Extend, Value::Object(map), Value::Object(patch)) => {
+            apply(map, patch).map_err(Box::new)?
+        }
+        (_, target, value) => {
+            return Err(MergeErrorReason::Mismatch {
+                target: value_type(target),
+                operand: value_type(&value),
+            })
+        }
+    }
+    Ok(())
+}
+
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// The entities of a single content file, before they are deserialized.
+pub type RawData = BTreeMap<EntityKind, Vec<Map<String, Value>>>;
+
+/// Untyped entities of every source, merged in source order.
+#[derive(Debug, Default)]
+pub struct RawCompendium {
+    entities: BTreeMap<EntityKind, BTreeMap<String, Sourced<Map<String, Value>>>>,
+}
+
+impl RawCompendium {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or merge) every entity of `data`, which was loaded from `source`.
+    ///
+    /// `spans` are the spans of the entities of `data` in `source`, if they are known.
+    pub fn insert_data(
+        &mut self,
+        source: &SourceFile,
+        data: RawData,
+        spans: &Spans,
+    ) -> Result<(), MergeError> {
+        for (kind, entities) in data {
+            let spans = spans.get(&kind).map(Vec::as_slice).unwrap_or_default();
+            for (i, entity) in entities.into_iter().enumerate() {
+                self.insert_entity(source, kind, entity, spans.get(i).copied())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add (or merge) a single entity, which was loaded from `source`.
+    pub fn insert_entity(
+        &mut self,
+        source: &SourceFile,
+        kind: EntityKind,
+        entity: Map<String, Value>,
+        span: Option<Span>,
+    ) -> Result<(), MergeError> {
+        let id = find_key(&entity, "id")
+            .and_then(|key| entity[&key].as_str().map(ToOwned::to_owned))
+            .ok_or(MergeError {
+                key: "id".to_owned(),
+                op: None,
+                reason: MergeErrorReason::MissingId(kind),
+            })?;
+
+        let map = self.entities.entry(kind).or_default();
+        match map.get_mut(&id) {
+            Some(existing) if has_operators(&entity) => {
+                apply(&mut existing.value, entity)?;
+                existing.source = source.clone();
+                existing.span = span;
+            }
+            _ => {
+                let mut value = Map::new();
+                apply(&mut value, entity)?;
+                map.insert(
+                    id,
+                    Sourced {
+                        source: source.clone(),
+                        span,
+                        extra: Extra::new(),
+                        value,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Deserialize every entity, failing at the first one that doesn't match the data model.
+    ///
+    /// `unknown_fields` decides whether keys that aren't part of the data model count as a
+    /// mismatch.
+    pub fn into_compendium(
+        self,
+        unknown_fields: UnknownFields,
+    ) -> Result<Compendium, Box<EntityError>> {
+        let (compendium, errors) = self.into_compendium_partial(unknown_fields);
+        match errors.into_iter().next() {
+            Some(err) => Err(Box::new(err)),
+            None => Ok(compendium),
+        }
+    }
+
+    /// Deserialize every entity, skipping the ones that don't match the data model.
+    pub fn into_compendium_partial(
+        mut self,
+        unknown_fields: UnknownFields,
+    ) -> (Compendium, Vec<EntityError>) {
+        let mut errors = Vec::new();
+        let compendium = Compendium {
+            achievements: self.typed(unknown_fields, &mut errors),
+            cultures: self.typed(unknown_fields, &mut errors),
+            decks: self.typed(unknown_fields, &mut errors),
+            dicta: self.typed(unknown_fields, &mut errors),
+            elements: self.typed(unknown_fields, &mut errors),
+            endings: self.typed(unknown_fields, &mut errors),
+            legacies: self.typed(unknown_fields, &mut errors),
+            levers: self.typed(unknown_fields, &mut errors),
+            portals: self.typed(unknown_fields, &mut errors),
+            recipes: self.typed(unknown_fields, &mut errors),
+            settings: self.typed(unknown_fields, &mut errors),
+            verbs: self.typed(unknown_fields, &mut errors),
+        };
+        (compendium, errors)
+    }
+
+    fn typed<T: Entity + DeserializeOwned>(
+        &mut self,
+        unknown_fields: UnknownFields,
+        errors: &mut Vec<EntityError>,
+    ) -> BTreeMap<String, Sourced<T>> {
+        let mut typed = BTreeMap::new();
+        for (id, entity) in mem::take(self.entities.entry(T::KIND).or_default()) {
+            let Sourced {
+                source,
+                span,
+                value,
+                ..
+            } = entity;
+            let value = Value::Object(value);
+            let result = match unknown_fields {
+                UnknownFields::Strict => serde_json::from_value(value).map(|t| (t, Extra::new())),
+                UnknownFields::Capture => from_value_lenient(value),
+                UnknownFields::Ignore => from_value_lenient(value).map(|(t, _)| (t, Extra::new())),
+            };
+            match result {
+                Ok((value, extra)) => {
+                    typed.insert(
+                        id,
+                        Sourced {
+                            source,
+                            span,
+                            extra,
+                            value,
+                        },
+                    );
+                }
+                Err(error) => errors.push(EntityError {
+                    kind: T::KIND,
+                    id,
+                    source,
+                    span,
+                    error,
+                }),
+            }
+        }
+        typed
+    }
+}
+
+/// A (merged) entity that doesn't match the data model.
+#[derive(Debug)]
+pub struct EntityError {
+    pub kind: EntityKind,
+    pub id: String,
+    /// The file the entity was last defined or patched in.
+    pub source: SourceFile,
+    pub span: Option<Span>,
+    pub error: serde_json::Error,
+}
+
+impl Display for EntityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.kind, self.id, self.error)
+    }
+}
+
+impl Error for EntityError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Merging `key` failed.
+#[derive(Debug)]
+pub struct MergeError {
+    pub key: String,
+    pub op: Option<MergeOp>,
+    pub reason: MergeErrorReason,
+}
+
+#[derive(Debug)]
+pub enum MergeErrorReason {
+    /// The operator can't be applied to the existing value with this operand.
+    Mismatch {
+        target: &'static str,
+        operand: &'static str,
+    },
+    /// The operand has a type the operator doesn't accept.
+    Operand(&'static str),
+    /// An entity has no `id` to merge it by.
+    MissingId(EntityKind),
+    /// A nested `$extend` failed.
+    Nested(Box<MergeError>),
+}
+
+impl From<Box<MergeError>> for MergeErrorReason {
+    fn from(value: Box<MergeError>) -> Self {
+        MergeErrorReason::Nested(value)
+    }
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = self.op.map(MergeOp::as_str).unwrap_or("merge");
+        match &self.reason {
+            MergeErrorReason::Mismatch { target, operand } => write!(
+                f,
+                "{:?}: can't apply {op} with {operand} to {target}",
+                self.key
+            ),
+            MergeErrorReason::Operand(operand) => {
+                write!(f, "{:?}: invalid operand {operand} for {op}", self.key)
+            }
+            MergeErrorReason::MissingId(kind) => write!(f, "{kind} entity without an id"),
+            MergeErrorReason::Nested(err) => write!(f, "{:?}: {err}", self.key),
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            value => panic!("not a map: {value}"),
+        }
+    }
+
+    /// `base` with `patch` applied to it.
+    fn patched(base: Value, patch: Value) -> Value {
+        let mut base = map(base);
+        apply(&mut base, map(patch)).unwrap();
+        Value::Object(base)
+    }
+
+    fn error(base: Value, patch: Value) -> MergeError {
+        apply(&mut map(base), map(patch)).unwrap_err()
+    }
+
+    #[test]
+    fn split_key() {
+        assert_eq!(
+            MergeOp::split_key("slots$add"),
+            ("slots", Some(MergeOp::Add))
+        );
+        assert_eq!(
+            MergeOp::split_key("label$PREFIX"),
+            ("label", Some(MergeOp::Prefix))
+        );
+        assert_eq!(MergeOp::split_key("slots$other"), ("slots$other", None));
+        assert_eq!(MergeOp::split_key("$derives"), ("$derives", None));
+        assert_eq!(MergeOp::split_key("$add"), ("$add", None));
+        assert!(!has_operators(&map(json!({ "id": "a", "$derives": "b" }))));
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!(
+            patched(
+                json!({ "list": [1], "map": { "a": 1 } }),
+                json!({ "list$add": [2, 3], "map$add": { "b": 2 } }),
+            ),
+            json!({ "list": [1, 2, 3], "map": { "a": 1, "b": 2 } }),
+        );
+        assert_eq!(
+            patched(
+                json!({ "list": [1] }),
+                json!({ "list$add": 2, "new$add": [1] })
+            ),
+            json!({ "list": [1, 2], "new": [1] }),
+        );
+    }
+
+    #[test]
+    fn remove() {
+        assert_eq!(
+            patched(
+                json!({ "one": [1, 2, 1], "many": [1, 2, 3], "map": { "a": 1, "B": 2, "c": 3 } }),
+                json!({ "one$remove": 1, "many$remove": [1, 3], "map$remove": ["a", "b"] }),
+            ),
+            json!({ "one": [2], "many": [2], "map": { "c": 3 } }),
+        );
+        assert_eq!(
+            patched(
+                json!({ "map": { "a": 1 } }),
+                json!({ "map$remove": "A", "gone$remove": 1 })
+            ),
+            json!({ "map": {} }),
+        );
+    }
+
+    #[test]
+    fn prefix_and_postfix() {
+        assert_eq!(
+            patched(
+                json!({ "label": "Name" }),
+                json!({ "label$prefix": "The ", "label$postfix": "!", "desc$postfix": "new" }),
+            ),
+            json!({ "label": "The Name!", "desc": "new" }),
+        );
+    }
+
+    #[test]
+    fn nested_extend() {
+        assert_eq!(
+            patched(
+                json!({ "aspects": { "edge": 1, "inner": { "list": [1] } } }),
+                json!({
+                    "aspects$extend": {
+                        "heart": 2,
+                        "inner$extend": { "list$add": 2 },
+                        "new$extend": { "a$add": [1] },
+                    },
+                }),
+            ),
+            json!({
+                "aspects": {
+                    "edge": 1,
+                    "inner": { "list": [1, 2] },
+                    "heart": 2,
+                    "new": { "a": [1] },
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn case_insensitive_keys() {
+        assert_eq!(
+            patched(
+                json!({ "Slots": [1], "Label": "a" }),
+                json!({ "slots$add": 2, "LABEL": "b" }),
+            ),
+            json!({ "Slots": [1, 2], "Label": "b" }),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = error(json!({ "label": "a" }), json!({ "label$add": 1 }));
+        assert_eq!(err.key, "label$add");
+        assert_eq!(err.op, Some(MergeOp::Add));
+        assert!(matches!(
+            err.reason,
+            MergeErrorReason::Mismatch {
+                target: "string",
+                operand: "number"
+            }
+        ));
+
+        let err = error(json!({ "map": { "a": 1 } }), json!({ "map$remove": [1] }));
+        assert!(matches!(err.reason, MergeErrorReason::Operand("number")));
+        let err = error(json!({}), json!({ "map$extend": [] }));
+        assert!(matches!(err.reason, MergeErrorReason::Operand("array")));
+
+        let err = error(
+            json!({ "map": { "label": "a" } }),
+            json!({ "map$extend": { "label$add": 1 } }),
+        );
+        let MergeErrorReason::Nested(nested) = err.reason else {
+            panic!("{err}");
+        };
+        assert_eq!(nested.key, "label$add");
+    }
+
+    fn insert(raw: &mut RawCompendium, path: &str, entity: Value) -> Result<(), MergeError> {
+        let source = SourceFile {
+            root: "root".into(),
+            path: path.into(),
+        };
+        raw.insert_entity(&source, EntityKind::Elements, map(entity), None)
+    }
+
+    fn element<'a>(raw: &'a RawCompendium, id: &str) -> &'a Sourced<Map<String, Value>> {
+        &raw.entities[&EntityKind::Elements][id]
+    }
+
+    #[test]
+    fn replace_or_patch() {
+        let mut raw = RawCompendium::new();
+        insert(
+            &mut raw,
+            "core.json",
+            json!({ "id": "a", "label": "A", "aspects": { "x": 1 } }),
+        )
+        .unwrap();
+        insert(
+            &mut raw,
+            "patch.json",
+            json!({ "ID": "a", "aspects$add": { "y": 1 } }),
+        )
+        .unwrap();
+        assert_eq!(
+            Value::Object(element(&raw, "a").value.clone()),
+            json!({ "id": "a", "label": "A", "aspects": { "x": 1, "y": 1 } }),
+        );
+        assert_eq!(
+            element(&raw, "a").source.path,
+            std::path::Path::new("patch.json")
+        );
+
+        // Without operators, a later definition replaces the earlier one
+        insert(&mut raw, "replace.json", json!({ "id": "a", "label": "B" })).unwrap();
+        assert_eq!(
+            Value::Object(element(&raw, "a").value.clone()),
+            json!({ "id": "a", "label": "B" }),
+        );
+
+        // `$derives` isn't an operator either
+        insert(
+            &mut raw,
+            "derives.json",
+            json!({ "id": "a", "$derives": "b" }),
+        )
+        .unwrap();
+        assert_eq!(
+            Value::Object(element(&raw, "a").value.clone()),
+            json!({ "id": "a", "$derives": "b" }),
+        );
+    }
+
+    #[test]
+    fn missing_id() {
+        let mut raw = RawCompendium::new();
+        let err = insert(&mut raw, "core.json", json!({ "label": "A" })).unwrap_err();
+        assert!(matches!(
+            err.reason,
+            MergeErrorReason::MissingId(EntityKind::Elements)
+        ));
+        let err = insert(&mut raw, "core.json", json!({ "id": 1 })).unwrap_err();
+        assert_eq!(err.to_string(), "elements entity without an id");
+    }
+}
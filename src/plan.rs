@@ -0,0 +1,549 @@
+//! Search backwards from an element (or aspect) for the actions that produce it.
+//!
+//! Every [`Step`] produces one id out of its inputs, a plan chains steps by producing the inputs
+//! of later steps with earlier ones. Inputs are matched by id only: a requirement for an aspect
+//! is only expanded through steps that produce that exact aspect (like mutations), not through
+//! every element that has it.
+
+use crate::{
+    compendium::Compendium,
+    data::{
+        Elements, MorphEffect, Recipes, RecipesMutations, StringMapOrArray, StringOrI32,
+        StringOrStruct,
+    },
+    expr::EvalError,
+    simulate::Aspects,
+    xtrigger::transformations,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Display},
+};
+
+/// How a [`Step`] produces its output.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Action<'a> {
+    /// The recipe has the output in its `effects`.
+    Recipe { recipe: &'a str },
+    /// The recipe mutates the output onto the elements matching `filter`.
+    Mutation { recipe: &'a str, filter: &'a str },
+    /// The element is exposed to `catalyst`.
+    XTrigger {
+        element: &'a str,
+        catalyst: &'a str,
+        effect: MorphEffect,
+        chance: u32,
+    },
+    /// The element decays once its `lifetime` is over.
+    Decay { element: &'a str },
+    /// The element burns away.
+    Burn { element: &'a str },
+}
+
+impl Display for Action<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Recipe { recipe } => write!(f, "recipe {recipe}"),
+            Action::Mutation { recipe, filter } => write!(f, "recipe {recipe} mutating {filter}"),
+            Action::XTrigger {
+                element,
+                catalyst,
+                effect,
+                chance,
+            } => write!(
+                f,
+                "{} {element} with {catalyst} ({chance}%)",
+                effect.as_str()
+            ),
+            Action::Decay { element } => write!(f, "decay {element}"),
+            Action::Burn { element } => write!(f, "burn {element}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Step<'a> {
+    pub action: Action<'a>,
+    pub output: &'a str,
+    /// The ids (elements or aspects) the step needs and how many of each.
+    pub inputs: BTreeMap<&'a str, i32>,
+    pub verb: Option<&'a str>,
+    /// The `warmup` of the recipe or the `lifetime` of a decaying element.
+    pub warmup: u32,
+}
+
+impl Display for Step<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}", self.action, self.output)?;
+        if let Some(verb) = self.verb {
+            write!(f, " at {verb}")?;
+        }
+        if self.warmup > 0 {
+            write!(f, " in {}s", self.warmup)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan<'a> {
+    /// The steps in the order they have to happen, the last one produces the target.
+    pub steps: Vec<Step<'a>>,
+    /// The inputs that are neither produced by an earlier step nor available.
+    pub inputs: BTreeMap<&'a str, i32>,
+    pub verbs: BTreeSet<&'a str>,
+    pub warmup: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlanOptions {
+    /// The most steps a plan may have.
+    pub max_steps: usize,
+    /// The most plans to return.
+    pub max_plans: usize,
+    /// Aspects that are already at hand and don't need to be produced.
+    pub available: Aspects,
+}
+
+impl Default for PlanOptions {
+    fn default() -> Self {
+        Self {
+            max_steps: 3,
+            max_plans: 20,
+            available: Aspects::new(),
+        }
+    }
+}
+
+/// Whether the effect value `value` may be positive, expressions that depend on the situation
+/// are assumed to be.
+fn may_produce(value: &StringOrI32) -> bool {
+    match value.expr() {
+        Ok(expr) => match expr.eval(&|_: Option<&str>, _: &str| None) {
+            Ok(value) => value > 0,
+            Err(EvalError::Unresolved { .. }) => true,
+            Err(_) => false,
+        },
+        Err(_) => false,
+    }
+}
+
+/// The positive requirements of `recipe` that don't depend on the situation.
+fn recipe_inputs(recipe: &Recipes) -> BTreeMap<&str, i32> {
+    recipe
+        .requirements
+        .iter()
+        .flatten()
+        .filter_map(|(aspect, value)| {
+            let value = value.expr().ok()?.eval(&Aspects::new()).ok()?;
+            (value > 0).then_some((aspect.as_str(), value))
+        })
+        .collect()
+}
+
+fn mutations(recipe: &Recipes) -> Vec<&RecipesMutations> {
+    let mutations: Vec<_> = match &recipe.mutations {
+        None | Some(StringMapOrArray::Str(_)) => Vec::new(),
+        Some(StringMapOrArray::Map(mutation)) => vec![mutation],
+        Some(StringMapOrArray::Arr(mutations)) => mutations.iter().collect(),
+    };
+    mutations
+        .into_iter()
+        .filter_map(|mutation| match mutation {
+            StringOrStruct::Str(_) => None,
+            StringOrStruct::Struct(mutation) => Some(mutation),
+        })
+        .collect()
+}
+
+/// Every step that produces `id`.
+pub fn producers<'a>(compendium: &'a Compendium, id: &str) -> Vec<Step<'a>> {
+    let mut steps = Vec::new();
+
+    for recipe in compendium.recipes.values() {
+        let verb = recipe.action_id.as_deref();
+        let warmup = recipe.warmup.unwrap_or(0);
+        if let Some((output, _)) = recipe
+            .effects
+            .iter()
+            .flatten()
+            .find(|(effect, value)| *effect == id && may_produce(value))
+        {
+            steps.push(Step {
+                action: Action::Recipe { recipe: &recipe.id },
+                output,
+                inputs: recipe_inputs(recipe),
+                verb,
+                warmup,
+            });
+        }
+        for mutation in mutations(recipe) {
+            if mutation.mutate != id || !may_produce(&mutation.level) {
+                continue;
+            }
+            let mut inputs = recipe_inputs(recipe);
+            inputs.entry(&mutation.filter).or_insert(1);
+            steps.push(Step {
+                action: Action::Mutation {
+                    recipe: &recipe.id,
+                    filter: &mutation.filter,
+                },
+                output: &mutation.mutate,
+                inputs,
+                verb,
+                warmup,
+            });
+        }
+    }
+
+    for element in compendium.elements.values() {
+        steps.extend(element_producers(element, id));
+    }
+    steps
+}
+
+fn element_producers<'a>(element: &'a Elements, id: &str) -> Vec<Step<'a>> {
+    let mut steps = Vec::new();
+    let from = |action, output, inputs, warmup| Step {
+        action,
+        output,
+        inputs,
+        verb: None,
+        warmup,
+    };
+
+    let catalysts: Aspects = element
+        .xtriggers
+        .iter()
+        .flatten()
+        .map(|(catalyst, _)| (catalyst.clone(), 1))
+        .collect();
+    for transformation in transformations(element, &catalysts) {
+        let Some(output) = transformation.target.filter(|target| *target == id) else {
+            continue;
+        };
+        // Like recipe mutations, a mutation that doesn't add the aspect doesn't produce it
+        let mutation = matches!(
+            transformation.effect,
+            MorphEffect::Mutate | MorphEffect::SetMutation
+        );
+        if mutation && transformation.level <= 0 {
+            continue;
+        }
+        let inputs = BTreeMap::from([(element.id.as_str(), 1), (transformation.catalyst, 1)]);
+        let action = Action::XTrigger {
+            element: &element.id,
+            catalyst: transformation.catalyst,
+            effect: transformation.effect,
+            chance: transformation.chance,
+        };
+        steps.push(from(action, output, inputs, 0));
+    }
+
+    if let Some(output) = element.decay_to.as_deref().filter(|decay| *decay == id) {
+        let action = Action::Decay {
+            element: &element.id,
+        };
+        let lifetime = element.lifetime.unwrap_or(0.0).max(0.0).ceil() as u32;
+        let inputs = BTreeMap::from([(element.id.as_str(), 1)]);
+        steps.push(from(action, output, inputs, lifetime));
+    }
+    if let Some(output) = element.burn_to.as_deref().filter(|burn| *burn == id) {
+        let action = Action::Burn {
+            element: &element.id,
+        };
+        let inputs = BTreeMap::from([(element.id.as_str(), 1)]);
+        steps.push(from(action, output, inputs, 0));
+    }
+    steps
+}
+
+impl<'a> Plan<'a> {
+    fn new(steps: Vec<Step<'a>>, available: &Aspects) -> Self {
+        let produced: BTreeSet<&str> = steps.iter().map(|step| step.output).collect();
+        let mut inputs = BTreeMap::new();
+        for step in &steps {
+            for (&input, &amount) in &step.inputs {
+                let have = available.get(input).copied().unwrap_or(0);
+                if !produced.contains(input) && have < amount {
+                    let needed: &mut i32 = inputs.entry(input).or_default();
+                    *needed = (*needed).max(amount);
+                }
+            }
+        }
+        Self {
+            verbs: steps.iter().filter_map(|step| step.verb).collect(),
+            warmup: steps.iter().map(|step| step.warmup).sum(),
+            inputs,
+            steps,
+        }
+    }
+
+    fn key(&self) -> BTreeSet<String> {
+        self.steps
+            .iter()
+            .map(|step| format!("{} -> {}", step.action, step.output))
+            .collect()
+    }
+}
+
+/// Search for plans that produce `target`, fewest missing inputs first, then fewest steps, then
+/// shortest warmup.
+pub fn plan<'a>(compendium: &'a Compendium, target: &str, options: &PlanOptions) -> Vec<Plan<'a>> {
+    // Steps are searched breadth first, so every plan is only ever extended by a step that
+    // produces one of its open inputs
+    let mut cache: BTreeMap<String, Vec<Step<'a>>> = BTreeMap::new();
+    let mut producers_of = |id: &str| {
+        cache
+            .entry(id.to_owned())
+            .or_insert_with(|| producers(compendium, id))
+            .clone()
+    };
+
+    let budget = options.max_plans.saturating_mul(50).max(1000);
+    let mut seen = BTreeSet::new();
+    let mut plans = Vec::new();
+    let mut queue: VecDeque<Vec<Step<'a>>> = producers_of(target)
+        .into_iter()
+        .filter(|step| !step.inputs.contains_key(target))
+        .map(|step| vec![step])
+        .collect();
+
+    while let Some(steps) = queue.pop_front() {
+        if plans.len() >= budget {
+            break;
+        }
+        let plan = Plan::new(steps, &options.available);
+        if !seen.insert(plan.key()) {
+            continue;
+        }
+
+        if plan.steps.len() < options.max_steps {
+            let produced: BTreeSet<&str> = plan.steps.iter().map(|step| step.output).collect();
+            for &input in plan.inputs.keys() {
+                for step in producers_of(input) {
+                    if step.inputs.contains_key(input)
+                        || step.inputs.keys().any(|input| produced.contains(input))
+                    {
+                        continue;
+                    }
+                    let mut steps = Vec::with_capacity(plan.steps.len() + 1);
+                    steps.push(step);
+                    steps.extend(plan.steps.iter().cloned());
+                    queue.push_back(steps);
+                }
+            }
+        }
+        plans.push(plan);
+    }
+
+    plans.sort_by_key(|plan| (plan.inputs.len(), plan.steps.len(), plan.warmup));
+    plans.truncate(options.max_plans);
+    plans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compendium::SourceFile, data::Data};
+    use serde_json::json;
+
+    fn compendium() -> Compendium {
+        let mut compendium = Compendium::new();
+        let data: Data = serde_json::from_value(json!({
+            "elements": [
+                { "id": "ember", "lifetime": 5.5, "decayTo": "ash" },
+                { "id": "log", "burnTo": "ash" },
+                {
+                    "id": "ore",
+                    "xtriggers": {
+                        "heat": "metal",
+                        "cool": [{ "id": "ash", "morpheffect": "mutate", "level": 0 }],
+                        "soot": [
+                            { "id": "ash", "morpheffect": "mutate", "level": 2, "chance": 50 },
+                            { "id": "ash", "morpheffect": "setmutation", "level": -1 },
+                        ],
+                    },
+                },
+            ],
+            "recipes": [
+                {
+                    "id": "forge",
+                    "actionid": "work",
+                    "warmup": 30,
+                    "reqs": { "metal": 1, "fire": 2, "dread": -1 },
+                    "effects": { "blade": 1 },
+                },
+                { "id": "break", "actionid": "work", "effects": { "blade": -1 } },
+                { "id": "scry", "actionid": "dream", "effects": { "blade": "[~/exterior:blade]" } },
+                {
+                    "id": "smelt",
+                    "actionid": "work",
+                    "warmup": 10,
+                    "reqs": { "ore": 1 },
+                    "effects": { "metal": 1 },
+                },
+                {
+                    "id": "temper",
+                    "actionid": "work",
+                    "mutations": [
+                        { "filter": "blade", "mutate": "sharp", "level": 1 },
+                        { "filter": "blade", "mutate": "ash", "level": -1 },
+                    ],
+                },
+                {
+                    "id": "brew",
+                    "actionid": "work",
+                    "reqs": { "herb": 1, "water": 1 },
+                    "effects": { "potion": 1 },
+                },
+                { "id": "gather", "actionid": "explore", "effects": { "herb": 1 } },
+                { "id": "draw", "actionid": "explore", "effects": { "water": 1 } },
+            ],
+        }))
+        .unwrap();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "test.json".into(),
+        };
+        compendium.insert_data(&source, data);
+        compendium
+    }
+
+    fn steps(steps: &[Step]) -> Vec<String> {
+        steps.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn effects_and_mutations() {
+        let compendium = compendium();
+        let blade = producers(&compendium, "blade");
+        // A negative effect doesn't produce, one that depends on the situation might
+        assert_eq!(
+            steps(&blade),
+            [
+                "recipe forge -> blade at work in 30s",
+                "recipe scry -> blade at dream"
+            ]
+        );
+        assert_eq!(blade[0].inputs, BTreeMap::from([("fire", 2), ("metal", 1)]));
+
+        let sharp = producers(&compendium, "sharp");
+        assert_eq!(
+            steps(&sharp),
+            ["recipe temper mutating blade -> sharp at work"]
+        );
+        assert_eq!(sharp[0].inputs, BTreeMap::from([("blade", 1)]));
+    }
+
+    #[test]
+    fn element_steps() {
+        let compendium = compendium();
+        let ash = producers(&compendium, "ash");
+        // Neither the recipe mutation with a negative level nor the xtrigger mutations without a
+        // positive level produce ash
+        assert_eq!(
+            steps(&ash),
+            [
+                "decay ember -> ash in 6s",
+                "burn log -> ash",
+                "mutate ore with soot (50%) -> ash",
+            ]
+        );
+        assert_eq!(ash[0].warmup, 6);
+        assert_eq!(ash[2].inputs, BTreeMap::from([("ore", 1), ("soot", 1)]));
+
+        let metal = producers(&compendium, "metal");
+        assert_eq!(
+            steps(&metal),
+            [
+                "recipe smelt -> metal at work in 10s",
+                "transform ore with heat (100%) -> metal",
+            ]
+        );
+    }
+
+    #[test]
+    fn plans() {
+        let compendium = compendium();
+        let options = PlanOptions {
+            available: Aspects::from([("fire".to_owned(), 1)]),
+            ..PlanOptions::default()
+        };
+        let plans = plan(&compendium, "blade", &options);
+        let summary: Vec<_> = (plans.iter())
+            .map(|plan| (steps(&plan.steps), plan.inputs.clone(), plan.warmup))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    vec!["recipe scry -> blade at dream".to_owned()],
+                    BTreeMap::new(),
+                    0,
+                ),
+                (
+                    vec!["recipe forge -> blade at work in 30s".to_owned()],
+                    BTreeMap::from([("fire", 2), ("metal", 1)]),
+                    30,
+                ),
+                (
+                    vec![
+                        "recipe smelt -> metal at work in 10s".to_owned(),
+                        "recipe forge -> blade at work in 30s".to_owned(),
+                    ],
+                    BTreeMap::from([("fire", 2), ("ore", 1)]),
+                    40,
+                ),
+                (
+                    vec![
+                        "transform ore with heat (100%) -> metal".to_owned(),
+                        "recipe forge -> blade at work in 30s".to_owned(),
+                    ],
+                    BTreeMap::from([("fire", 2), ("heat", 1), ("ore", 1)]),
+                    30,
+                ),
+            ]
+        );
+        assert_eq!(plans[2].verbs, BTreeSet::from(["work"]));
+    }
+
+    #[test]
+    fn identical_plans_are_deduplicated() {
+        let compendium = compendium();
+        let plans = plan(&compendium, "potion", &PlanOptions::default());
+        let summary: Vec<_> = plans.iter().map(|plan| steps(&plan.steps)).collect();
+        // Gathering before or after drawing water is the same plan
+        assert_eq!(
+            summary,
+            [
+                vec![
+                    "recipe draw -> water at explore",
+                    "recipe gather -> herb at explore",
+                    "recipe brew -> potion at work",
+                ],
+                vec![
+                    "recipe gather -> herb at explore",
+                    "recipe brew -> potion at work",
+                ],
+                vec![
+                    "recipe draw -> water at explore",
+                    "recipe brew -> potion at work",
+                ],
+                vec!["recipe brew -> potion at work"],
+            ]
+        );
+        assert_eq!(plans[0].verbs, BTreeSet::from(["explore", "work"]));
+
+        let options = PlanOptions {
+            max_plans: 2,
+            ..PlanOptions::default()
+        };
+        let best = plan(&compendium, "potion", &options);
+        assert_eq!(best.len(), 2);
+        assert_eq!(steps(&best[0].steps), summary[0]);
+        assert_eq!(steps(&best[1].steps), summary[1]);
+    }
+}
@@ -1,8 +1,17 @@
-use crate::config::ResolvedSourceFormat;
+use crate::{
+    config::ResolvedSourceFormat,
+    span::{entity_spans, Spans},
+    strict::{find_extensions, NonStandardJson},
+};
 use anyhow::Result;
+use encoding_rs::{Encoding, UTF_8};
 use encoding_rs_io::DecodeReaderBytesBuilder;
 use serde::de::DeserializeOwned;
-use std::{fs::File, io::BufReader, path::Path};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
 
 pub struct Reader {
     buf: Vec<u8>,
@@ -21,11 +30,18 @@ impl Reader {
         }
     }
 
+    /// Deserialize the file at `path`, with the extensions enabled by `format`.
     pub fn deserialize_from<T: DeserializeOwned>(
         &mut self,
         path: &Path,
         format: ResolvedSourceFormat,
     ) -> Result<T> {
+        if format.strict {
+            let text = self.read_to_string(path, format)?;
+            Self::check_strict(&text, format)?;
+            return Ok(Self::deserialize_str(&text, format)?);
+        }
+
         let reader = DecodeReaderBytesBuilder::new()
             .encoding(format.encoding)
             .strip_bom(true) // Strip bom, even if encoding is explicitly set
@@ -33,9 +49,74 @@ impl Reader {
             .build_with_buffer(BufReader::new(File::open(path)?), &mut self.buf)?;
 
         let mut de = serde_json::Deserializer::from_reader(reader);
-        de.allow_trailing_comma();
+        if format.allow_trailing_comma {
+            de.allow_trailing_comma();
+        }
+        let t = T::deserialize(&mut de)?;
+        de.end()?;
+        Ok(t)
+    }
+
+    /// Decode the whole file as UTF-8.
+    pub fn read_to_string(&mut self, path: &Path, format: ResolvedSourceFormat) -> Result<String> {
+        let mut reader = DecodeReaderBytesBuilder::new()
+            .encoding(format.encoding)
+            .strip_bom(true)
+            .bom_sniffing(format.autodetect)
+            .build_with_buffer(BufReader::new(File::open(path)?), &mut self.buf)?;
+
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    /// Like [`Self::deserialize_from`], but also find the [`Spans`] of the top-level entities.
+    pub fn deserialize_with_spans<T: DeserializeOwned>(
+        &mut self,
+        path: &Path,
+        format: ResolvedSourceFormat,
+    ) -> Result<(T, Spans)> {
+        let text = self.read_to_string(path, format)?;
+        Self::check_strict(&text, format)?;
+        let t = Self::deserialize_str(&text, format)?;
+        Ok((t, entity_spans(&text)))
+    }
+
+    /// Deserialize already decoded text, with the extensions enabled by `format`.
+    ///
+    /// This doesn't check [`ResolvedSourceFormat::strict`], see [`Self::check_strict`] for that.
+    pub fn deserialize_str<T: DeserializeOwned>(
+        text: &str,
+        format: ResolvedSourceFormat,
+    ) -> serde_json::Result<T> {
+        let mut de = serde_json::Deserializer::from_str(text);
+        if format.allow_trailing_comma {
+            de.allow_trailing_comma();
+        }
         let t = T::deserialize(&mut de)?;
         de.end()?;
         Ok(t)
     }
+
+    /// Fail if `format` is strict and `text` uses any extension to standard JSON.
+    pub fn check_strict(text: &str, format: ResolvedSourceFormat) -> Result<(), NonStandardJson> {
+        if !format.strict {
+            return Ok(());
+        }
+        let uses = find_extensions(text);
+        match uses.is_empty() {
+            true => Ok(()),
+            false => Err(NonStandardJson { uses }),
+        }
+    }
+
+    /// The encoding a file starting with `bytes` is decoded with.
+    pub fn resolved_encoding(bytes: &[u8], format: ResolvedSourceFormat) -> &'static Encoding {
+        let bom = Encoding::for_bom(bytes).filter(|_| format.autodetect);
+        match (bom, format.encoding) {
+            (Some((encoding, _)), _) => encoding,
+            (None, Some(encoding)) => encoding,
+            (None, None) => UTF_8,
+        }
+    }
 }
@@ -0,0 +1,467 @@
+//! Every place an entity refers to another entity by `id`.
+
+use crate::{
+    compendium::{Compendium, SourceFile, Sourced},
+    data::{
+        Achievements, Decks, Elements, ElementsSlots, Endings, Entity, EntityKind, Legacies,
+        Portals, Recipes, RecipesLinkedExpulsion, RecipesMutations, RecipesPreslots, RecipesSlots,
+        StringMapOrArray, StringOrStringArray, StringOrStruct, Verbs, VerbsSlot, XTriggerTarget,
+    },
+    span::Span,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A reference from the entity `from_kind[from_id]` at `path` to `to_kind[to_id]`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Reference<'a> {
+    pub from_kind: EntityKind,
+    pub from_id: &'a str,
+    pub source: &'a SourceFile,
+    /// Where the referring entity is in [`Self::source`].
+    pub span: Option<Span>,
+    /// The field path inside the referring entity, like `slots[0].forbidden`.
+    pub path: String,
+    pub to_kind: EntityKind,
+    pub to_id: &'a str,
+}
+
+/// Collect the references of every entity in `compendium`.
+///
+/// Aspects are elements, so they are referenced as [`EntityKind::Elements`]. Expression keys
+/// (like `[~/exterior : aspect]`) and the empty id are skipped.
+pub fn collect_references(compendium: &Compendium) -> Vec<Reference<'_>> {
+    let mut out = Vec::new();
+    collect_all(&mut out, &compendium.achievements);
+    collect_all(&mut out, &compendium.decks);
+    collect_all(&mut out, &compendium.elements);
+    collect_all(&mut out, &compendium.endings);
+    collect_all(&mut out, &compendium.legacies);
+    collect_all(&mut out, &compendium.portals);
+    collect_all(&mut out, &compendium.recipes);
+    collect_all(&mut out, &compendium.verbs);
+    out
+}
+
+/// An entity that refers to other entities.
+pub trait References: Entity {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>);
+}
+
+fn collect_all<'a, T: References>(
+    out: &mut Vec<Reference<'a>>,
+    entities: &'a BTreeMap<String, Sourced<T>>,
+) {
+    for entity in entities.values() {
+        entity.references(&mut Collector {
+            out,
+            from_kind: T::KIND,
+            from_id: entity.id(),
+            source: &entity.source,
+            span: entity.span,
+        });
+    }
+}
+
+pub struct Collector<'a, 'b> {
+    out: &'b mut Vec<Reference<'a>>,
+    from_kind: EntityKind,
+    from_id: &'a str,
+    source: &'a SourceFile,
+    span: Option<Span>,
+}
+
+impl<'a> Collector<'a, '_> {
+    pub fn id(&mut self, to_kind: EntityKind, path: &str, to_id: &'a str) {
+        if to_id.is_empty() || to_id.starts_with('[') {
+            return;
+        }
+        self.out.push(Reference {
+            from_kind: self.from_kind,
+            from_id: self.from_id,
+            source: self.source,
+            span: self.span,
+            path: path.to_owned(),
+            to_kind,
+            to_id,
+        });
+    }
+
+    pub fn opt(&mut self, to_kind: EntityKind, path: &str, to_id: &'a Option<String>) {
+        if let Some(to_id) = to_id {
+            self.id(to_kind, path, to_id);
+        }
+    }
+
+    pub fn ids(&mut self, to_kind: EntityKind, path: &str, to_ids: &'a [String]) {
+        for to_id in to_ids {
+            self.id(to_kind, path, to_id);
+        }
+    }
+
+    pub fn opt_ids(&mut self, to_kind: EntityKind, path: &str, to_ids: &'a Option<Vec<String>>) {
+        if let Some(to_ids) = to_ids {
+            self.ids(to_kind, path, to_ids);
+        }
+    }
+
+    pub fn keys<V>(&mut self, to_kind: EntityKind, path: &str, map: &'a BTreeMap<String, V>) {
+        for to_id in map.keys() {
+            self.id(to_kind, path, to_id);
+        }
+    }
+
+    pub fn opt_keys<V>(
+        &mut self,
+        to_kind: EntityKind,
+        path: &str,
+        map: &'a Option<BTreeMap<String, V>>,
+    ) {
+        if let Some(map) = map {
+            self.keys(to_kind, path, map);
+        }
+    }
+
+    /// A `StringMapOrArray<StringOrStruct<T>>`, where the string form is the id itself.
+    fn string_map_or_array<T>(
+        &mut self,
+        to_kind: EntityKind,
+        path: &str,
+        value: &'a StringMapOrArray<StringOrStruct<T>>,
+        mut f: impl FnMut(&mut Self, &str, &'a T),
+    ) {
+        match value {
+            StringMapOrArray::Str(to_id) => self.id(to_kind, path, to_id),
+            StringMapOrArray::Map(value) => self.string_or_struct(to_kind, path, value, &mut f),
+            StringMapOrArray::Arr(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    self.string_or_struct(to_kind, &format!("{path}[{i}]"), value, &mut f);
+                }
+            }
+        }
+    }
+
+    fn string_or_struct<T>(
+        &mut self,
+        to_kind: EntityKind,
+        path: &str,
+        value: &'a StringOrStruct<T>,
+        f: &mut impl FnMut(&mut Self, &str, &'a T),
+    ) {
+        match value {
+            StringOrStruct::Str(to_id) => self.id(to_kind, path, to_id),
+            StringOrStruct::Struct(value) => f(self, path, value),
+        }
+    }
+
+    fn slot(
+        &mut self,
+        path: &str,
+        required: &'a Option<BTreeMap<String, impl Sized>>,
+        essential: &'a Option<BTreeMap<String, impl Sized>>,
+        forbidden: &'a Option<BTreeMap<String, impl Sized>>,
+    ) {
+        self.opt_keys(EntityKind::Elements, &format!("{path}.required"), required);
+        self.opt_keys(
+            EntityKind::Elements,
+            &format!("{path}.essential"),
+            essential,
+        );
+        self.opt_keys(
+            EntityKind::Elements,
+            &format!("{path}.forbidden"),
+            forbidden,
+        );
+    }
+
+    fn mutations(
+        &mut self,
+        path: &str,
+        mutations: &'a Option<StringMapOrArray<StringOrStruct<RecipesMutations>>>,
+    ) {
+        if let Some(mutations) = mutations {
+            self.string_map_or_array(EntityKind::Elements, path, mutations, |refs, path, m| {
+                refs.id(EntityKind::Elements, &format!("{path}.filter"), &m.filter);
+                refs.id(EntityKind::Elements, &format!("{path}.mutate"), &m.mutate);
+            });
+        }
+    }
+
+    fn expulsion(&mut self, path: &str, expulsion: &'a Option<RecipesLinkedExpulsion>) {
+        if let Some(expulsion) = expulsion {
+            self.keys(
+                EntityKind::Elements,
+                &format!("{path}.filter"),
+                &expulsion.filter,
+            );
+        }
+    }
+}
+
+impl References for Achievements {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.opt(EntityKind::Achievements, "category", &self.category);
+    }
+}
+
+impl References for Decks {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.opt(EntityKind::Elements, "defaultcard", &self.default_card);
+        refs.opt_keys(EntityKind::Elements, "drawmessages", &self.draw_messages);
+        refs.ids(EntityKind::Elements, "spec", &self.spec);
+    }
+}
+
+impl References for Elements {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.ids(EntityKind::Achievements, "achievements", &self.achievements);
+        refs.keys(EntityKind::Elements, "ambits", &self.ambits);
+        refs.keys(EntityKind::Elements, "aspects", &self.aspects);
+        refs.opt(EntityKind::Elements, "burnTo", &self.burn_to);
+        refs.opt_ids(EntityKind::Elements, "commute", &self.commute);
+        refs.opt(EntityKind::Elements, "decayto", &self.decay_to);
+        for (i, induces) in self.induces.iter().flatten().enumerate() {
+            refs.id(
+                EntityKind::Recipes,
+                &format!("induces[{i}].id"),
+                &induces.id,
+            );
+        }
+        for (i, imms) in self.imms.iter().flatten().enumerate() {
+            refs.keys(
+                EntityKind::Elements,
+                &format!("imms[{i}].effects"),
+                &imms.effects,
+            );
+            refs.keys(EntityKind::Elements, &format!("imms[{i}].reqs"), &imms.reqs);
+        }
+        refs.opt(EntityKind::Elements, "inherits", &self.inherits);
+        refs.opt(EntityKind::Levers, "lever", &self.lever);
+        for (i, slot) in self.slots.iter().enumerate() {
+            elements_slot(refs, &format!("slots[{i}]"), slot);
+        }
+        refs.opt(
+            EntityKind::Elements,
+            "uniquenessgroup",
+            &self.uniqueness_group,
+        );
+        for (catalyst, xtrigger) in self.xtriggers.iter().flatten() {
+            refs.id(EntityKind::Elements, "xtriggers", catalyst);
+            let path = format!("xtriggers.{catalyst}");
+            refs.string_map_or_array(EntityKind::Elements, &path, xtrigger, |refs, path, x| {
+                if let XTriggerTarget::Element(id) = &x.id {
+                    refs.id(EntityKind::Elements, &format!("{path}.id"), id);
+                }
+            });
+        }
+    }
+}
+
+fn elements_slot<'a>(refs: &mut Collector<'a, '_>, path: &str, slot: &'a ElementsSlots) {
+    refs.id(
+        EntityKind::Verbs,
+        &format!("{path}.actionid"),
+        &slot.action_id,
+    );
+    refs.keys(
+        EntityKind::Elements,
+        &format!("{path}.required"),
+        &slot.required,
+    );
+    refs.keys(
+        EntityKind::Elements,
+        &format!("{path}.essential"),
+        &slot.essential,
+    );
+    refs.keys(
+        EntityKind::Elements,
+        &format!("{path}.forbidden"),
+        &slot.forbidden,
+    );
+    refs.opt_keys(
+        EntityKind::Elements,
+        &format!("{path}.ifaspectspresent"),
+        &slot.if_aspects_present,
+    );
+}
+
+impl References for Endings {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.opt_ids(EntityKind::Achievements, "achievements", &self.achievements);
+    }
+}
+
+impl References for Legacies {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.opt(EntityKind::Legacies, "$derives", &self.derives);
+        refs.opt_keys(EntityKind::Elements, "effects", &self.effects);
+        refs.opt_ids(
+            EntityKind::Legacies,
+            "excludesOnEnding",
+            &self.excludes_on_ending,
+        );
+        refs.id(EntityKind::Endings, "fromending", &self.from_ending);
+        refs.opt(EntityKind::Verbs, "startingverbid", &self.starting_verb_id);
+        for (i, element) in self.statusbar_elements.iter().flatten().enumerate() {
+            let path = format!("statusbarelements[{i}]");
+            match element {
+                StringOrStruct::Str(id) => refs.id(EntityKind::Elements, &path, id),
+                StringOrStruct::Struct(element) => match &element.ids {
+                    StringOrStringArray::Str(id) => {
+                        refs.id(EntityKind::Elements, &format!("{path}.ids"), id)
+                    }
+                    StringOrStringArray::Arr(ids) => {
+                        refs.ids(EntityKind::Elements, &format!("{path}.ids"), ids)
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl References for Portals {
+    /// The egress is the portal that leads back out of the otherworld. `otherworldid` names a
+    /// scene of the game, not an entity, so it isn't a reference.
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.id(EntityKind::Portals, "egressid", &self.egress_id);
+        for (i, consequence) in self.consequences.iter().flatten().enumerate() {
+            refs.opt_keys(
+                EntityKind::Decks,
+                &format!("consequences[{i}].deckeffects"),
+                &consequence.deckeffects,
+            );
+        }
+    }
+}
+
+impl References for Recipes {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.ids(EntityKind::Achievements, "achievements", &self.achievements);
+        refs.opt(EntityKind::Verbs, "actionid", &self.action_id);
+        for (i, alt) in self.alt.iter().flatten().enumerate() {
+            let path = format!("alt[{i}]");
+            refs.id(EntityKind::Recipes, &format!("{path}.id"), &alt.id);
+            refs.opt(
+                EntityKind::Verbs,
+                &format!("{path}.actionid"),
+                &alt.actionid,
+            );
+            refs.opt_keys(
+                EntityKind::Decks,
+                &format!("{path}.deckeffects"),
+                &alt.deck_effects,
+            );
+            refs.opt_keys(
+                EntityKind::Elements,
+                &format!("{path}.effects"),
+                &alt.effects,
+            );
+            refs.opt(EntityKind::Endings, &format!("{path}.ending"), &alt.ending);
+            refs.expulsion(&format!("{path}.expulsion"), &alt.expulsion);
+            refs.opt_keys(
+                EntityKind::Elements,
+                &format!("{path}.extantreqs"),
+                &alt.extant_reqs,
+            );
+            refs.mutations(&format!("{path}.mutations"), &alt.mutations);
+            refs.opt_keys(
+                EntityKind::Elements,
+                &format!("{path}.requirements"),
+                &alt.requirements,
+            );
+        }
+        refs.opt_keys(EntityKind::Elements, "aspects", &self.aspects);
+        refs.opt_keys(EntityKind::Decks, "deckeffects", &self.deck_effects);
+        refs.opt_keys(EntityKind::Verbs, "deleteverb", &self.deleteverb);
+        refs.opt_keys(EntityKind::Elements, "effects", &self.effects);
+        refs.opt(EntityKind::Endings, "ending", &self.ending);
+        refs.opt_keys(EntityKind::Elements, "extantreqs", &self.extant_reqs);
+        refs.opt_keys(EntityKind::Elements, "greq", &self.g_req);
+        refs.opt_keys(EntityKind::Verbs, "haltverb", &self.haltverb);
+        for (i, induction) in self.inductions.iter().flatten().enumerate() {
+            refs.id(
+                EntityKind::Recipes,
+                &format!("inductions[{i}].id"),
+                &induction.id,
+            );
+        }
+        refs.opt(EntityKind::Recipes, "inherits", &self.inherits);
+        if let Some(deck) = &self.internal_deck {
+            refs.opt(
+                EntityKind::Elements,
+                "internaldeck.defaultcard",
+                &deck.default_card,
+            );
+            refs.ids(EntityKind::Elements, "internaldeck.spec", &deck.spec);
+        }
+        if let Some(linked) = &self.linked {
+            refs.string_map_or_array(EntityKind::Recipes, "linked", linked, |refs, path, l| {
+                refs.id(EntityKind::Recipes, &format!("{path}.id"), &l.id);
+                refs.opt(EntityKind::Verbs, &format!("{path}.actionid"), &l.actionid);
+                refs.opt_keys(EntityKind::Elements, &format!("{path}.effects"), &l.effects);
+                refs.expulsion(&format!("{path}.expulsion"), &l.expulsion);
+                refs.opt_keys(
+                    EntityKind::Elements,
+                    &format!("{path}.extantreqs"),
+                    &l.extant_reqs,
+                );
+                refs.mutations(&format!("{path}.mutations"), &l.mutations);
+                refs.opt_keys(EntityKind::Elements, &format!("{path}.purge"), &l.purge);
+                refs.opt_keys(
+                    EntityKind::Elements,
+                    &format!("{path}.requirements"),
+                    &l.requirements,
+                );
+            });
+        }
+        refs.mutations("mutations", &self.mutations);
+        refs.opt_keys(EntityKind::Elements, "ngreq", &self.ng_req);
+        refs.opt(EntityKind::Portals, "portaleffect", &self.portal_effect);
+        for (i, slot) in self.preslots.iter().flatten().enumerate() {
+            recipes_preslot(refs, &format!("preslots[{i}]"), slot);
+        }
+        refs.opt_keys(EntityKind::Elements, "purge", &self.purge);
+        refs.opt_keys(EntityKind::Elements, "requirements", &self.requirements);
+        for (i, slot) in self.slots.iter().flatten().enumerate() {
+            recipes_slot(refs, &format!("slots[{i}]"), slot);
+        }
+        refs.opt_keys(EntityKind::Elements, "tablereqs", &self.table_reqs);
+    }
+}
+
+fn recipes_preslot<'a>(refs: &mut Collector<'a, '_>, path: &str, slot: &'a RecipesPreslots) {
+    refs.slot(path, &slot.required, &slot.essential, &slot.forbidden);
+}
+
+fn recipes_slot<'a>(refs: &mut Collector<'a, '_>, path: &str, slot: &'a RecipesSlots) {
+    refs.opt(
+        EntityKind::Verbs,
+        &format!("{path}.actionid"),
+        &slot.actionid,
+    );
+    refs.slot(path, &slot.required, &slot.essential, &slot.forbidden);
+}
+
+impl References for Verbs {
+    fn references<'a>(&'a self, refs: &mut Collector<'a, '_>) {
+        refs.opt_keys(EntityKind::Elements, "aspects", &self.aspects);
+        if let Some(slot) = &self.slot {
+            verbs_slot(refs, "slot", slot);
+        }
+        for (i, slot) in self.slots.iter().flatten().enumerate() {
+            verbs_slot(refs, &format!("slots[{i}]"), slot);
+        }
+        for (catalyst, to_id) in self.xtriggers.iter().flatten() {
+            refs.id(EntityKind::Elements, "xtriggers", catalyst);
+            refs.id(
+                EntityKind::Elements,
+                &format!("xtriggers.{catalyst}"),
+                to_id,
+            );
+        }
+    }
+}
+
+fn verbs_slot<'a>(refs: &mut Collector<'a, '_>, path: &str, slot: &'a VerbsSlot) {
+    refs.slot(path, &slot.required, &slot.essential, &slot.forbidden);
+}
@@ -0,0 +1,586 @@
+//! Which recipes fire for a verb and the cards slotted into it.
+//!
+//! Requirement values follow the engine: a positive value `n` requires at least `n` of the
+//! aspect, a negative value `-n` requires less than `n` of it (so `-1` requires its absence).
+//!
+//! The game doesn't document `ngreq`. Every `ngreq` in the content is `{ "<aspect>": 1 }`, on
+//! recipes that must not fire once something happened in the world, so it is treated as a negated
+//! `greq`: `{ "<aspect>": n }` requires less than `n` of the aspect globally, whatever its sign.
+//!
+//! Aspect totals saturate at the bounds of [`i32`] instead of overflowing.
+
+use crate::{
+    compendium::{Compendium, Sourced},
+    data::{Elements, Recipes, StringOrI32},
+    expr::{Context, EvalError, ParseError},
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+pub type Aspects = BTreeMap<String, i32>;
+
+/// The state a verb is started in.
+#[derive(Debug, Clone, Default)]
+pub struct Situation {
+    pub verb: String,
+    /// The slotted elements and how many of each.
+    pub slotted: BTreeMap<String, u32>,
+    /// Aspects of every card in play, checked by `extantreqs`.
+    pub extant: Aspects,
+    /// Aspects of the cards on the table, checked by `tablereqs`.
+    pub table: Aspects,
+    /// Aspects of the whole game world, checked by `greq` and `ngreq`.
+    pub global: Aspects,
+    /// Aspects of other paths like `~/exterior`, for expressions that refer to them.
+    pub paths: BTreeMap<String, Aspects>,
+}
+
+impl Situation {
+    pub fn new(verb: impl Into<String>) -> Self {
+        Self {
+            verb: verb.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The combined aspects of the slotted elements and the verb, where every element also
+    /// counts as one of its own id.
+    pub fn aspects(&self, compendium: &Compendium) -> Aspects {
+        let mut aspects = Aspects::new();
+        if let Some(verb_aspects) = compendium
+            .verbs
+            .get(&self.verb)
+            .and_then(|verb| verb.aspects.as_ref())
+        {
+            for (aspect, value) in verb_aspects {
+                add(&mut aspects, aspect.clone(), saturating_i32(*value));
+            }
+        }
+        for (id, &count) in &self.slotted {
+            let count = saturating_i32(count);
+            let element = match compendium.elements.get(id) {
+                Some(element) => element_aspects(element),
+                None => Aspects::from([(id.clone(), 1)]),
+            };
+            for (aspect, value) in element {
+                add(&mut aspects, aspect, value.saturating_mul(count));
+            }
+        }
+        aspects
+    }
+}
+
+/// The aspects of a single card of `element`, including one of its own id.
+pub fn element_aspects(element: &Elements) -> Aspects {
+    let mut aspects: Aspects = element
+        .aspects
+        .iter()
+        .map(|(aspect, &value)| (aspect.clone(), saturating_i32(value)))
+        .collect();
+    add(&mut aspects, element.id.clone(), 1);
+    aspects
+}
+
+fn add(aspects: &mut Aspects, aspect: String, value: i32) {
+    let total = aspects.entry(aspect).or_default();
+    *total = total.saturating_add(value);
+}
+
+fn saturating_i32(value: u32) -> i32 {
+    i32::try_from(value).unwrap_or(i32::MAX)
+}
+
+struct SituationContext<'a> {
+    situation: &'a Situation,
+    aspects: &'a Aspects,
+}
+
+impl Context for SituationContext<'_> {
+    fn aspect(&self, path: Option<&str>, aspect: &str) -> Option<i32> {
+        let aspects = match path {
+            None => self.aspects,
+            Some(path) => self.situation.paths.get(path)?,
+        };
+        Some(aspects.get(aspect).copied().unwrap_or(0))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Simulation<'a> {
+    /// Craftable recipes whose requirements are all met.
+    pub eligible: Vec<&'a Sourced<Recipes>>,
+    /// Hint-only recipes whose requirements are all met.
+    pub hints: Vec<&'a Sourced<Recipes>>,
+    /// Recipes that meet at least one positive requirement, but fail others.
+    pub near_misses: Vec<NearMiss<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NearMiss<'a> {
+    pub recipe: &'a Sourced<Recipes>,
+    pub failures: Vec<Failure>,
+}
+
+/// The requirement table of a recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReqTable {
+    Requirements,
+    ExtantReqs,
+    TableReqs,
+    GReq,
+    NgReq,
+}
+
+impl ReqTable {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReqTable::Requirements => "requirements",
+            ReqTable::ExtantReqs => "extantreqs",
+            ReqTable::TableReqs => "tablereqs",
+            ReqTable::GReq => "greq",
+            ReqTable::NgReq => "ngreq",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum Failure {
+    /// The aspect has `actual`, but `required` asks for a different amount.
+    Requirement {
+        table: ReqTable,
+        aspect: String,
+        required: i32,
+        actual: i32,
+    },
+    /// The requirement value is not a valid expression.
+    Parse {
+        aspect: String,
+        #[serde(serialize_with = "serialize_display")]
+        error: ParseError,
+    },
+    /// The requirement value couldn't be evaluated.
+    Eval {
+        aspect: String,
+        #[serde(serialize_with = "serialize_display")]
+        error: EvalError,
+    },
+}
+
+fn serialize_display<S: serde::Serializer>(
+    value: &impl Display,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Requirement {
+                table,
+                aspect,
+                required,
+                actual,
+            } if *required < 0 => write!(
+                f,
+                "{}: {aspect} needs less than {}, has {actual}",
+                table.as_str(),
+                -required
+            ),
+            Failure::Requirement {
+                table,
+                aspect,
+                required,
+                actual,
+            } => write!(
+                f,
+                "{}: {aspect} needs {required}, has {actual}",
+                table.as_str()
+            ),
+            Failure::Parse { aspect, error } => write!(f, "requirements: {aspect}: {error}"),
+            Failure::Eval { aspect, error } => write!(f, "requirements: {aspect}: {error}"),
+        }
+    }
+}
+
+/// Whether `actual` satisfies the requirement value `required`.
+pub fn satisfies(required: i32, actual: i32) -> bool {
+    match required {
+        ..0 => actual < required.saturating_neg(),
+        _ => actual >= required,
+    }
+}
+
+/// Whether the verb `verb` matches the `actionid` of a recipe, which may end in a `*` wildcard.
+pub fn matches_verb(action_id: &str, verb: &str) -> bool {
+    match action_id.strip_suffix('*') {
+        Some(prefix) => verb.starts_with(prefix),
+        None => action_id == verb,
+    }
+}
+
+/// Check every craftable or hint recipe of `situation.verb` against the situation.
+pub fn simulate<'a>(compendium: &'a Compendium, situation: &Situation) -> Simulation<'a> {
+    let aspects = situation.aspects(compendium);
+    let context = SituationContext {
+        situation,
+        aspects: &aspects,
+    };
+
+    let mut simulation = Simulation {
+        eligible: Vec::new(),
+        hints: Vec::new(),
+        near_misses: Vec::new(),
+    };
+    for recipe in compendium.recipes.values() {
+        let hint_only = recipe.hint_only == Some(true);
+        if !(recipe.craftable == Some(true) || hint_only) {
+            continue;
+        }
+        if !recipe
+            .action_id
+            .as_deref()
+            .is_some_and(|action_id| matches_verb(action_id, &situation.verb))
+        {
+            continue;
+        }
+
+        let (failures, any_met) = check_recipe(recipe, situation, &context);
+        match failures.is_empty() {
+            true if hint_only => simulation.hints.push(recipe),
+            true => simulation.eligible.push(recipe),
+            false if any_met => simulation.near_misses.push(NearMiss { recipe, failures }),
+            false => (),
+        }
+    }
+    simulation
+}
+
+/// Check all requirement tables of `recipe`, returns the failures and whether any positive
+/// requirement was met.
+fn check_recipe(
+    recipe: &Recipes,
+    situation: &Situation,
+    context: &SituationContext,
+) -> (Vec<Failure>, bool) {
+    let mut checker = Checker::default();
+
+    for (aspect, required) in recipe.requirements.iter().flatten() {
+        let actual = lookup(context.aspects, aspect);
+        match eval_requirement(aspect, required, context) {
+            Ok(required) => checker.check(ReqTable::Requirements, aspect, required, actual),
+            Err(failure) => checker.failures.push(failure),
+        }
+    }
+
+    let tables = [
+        (ReqTable::ExtantReqs, &situation.extant, &recipe.extant_reqs),
+        (ReqTable::GReq, &situation.global, &recipe.g_req),
+    ];
+    for (table, aspects, reqs) in tables {
+        for (aspect, &required) in reqs.iter().flatten() {
+            checker.check(table, aspect, required, lookup(aspects, aspect));
+        }
+    }
+    for (aspect, &required) in recipe.table_reqs.iter().flatten() {
+        let actual = lookup(&situation.table, aspect);
+        checker.check(
+            ReqTable::TableReqs,
+            aspect,
+            saturating_i32(required),
+            actual,
+        );
+    }
+    // A negated `greq`, see the module docs
+    for (aspect, &required) in recipe.ng_req.iter().flatten() {
+        let actual = lookup(&situation.global, aspect);
+        let required = required.saturating_abs().saturating_neg();
+        checker.check(ReqTable::NgReq, aspect, required, actual);
+    }
+
+    (checker.failures, checker.any_met)
+}
+
+#[derive(Default)]
+struct Checker {
+    failures: Vec<Failure>,
+    any_met: bool,
+}
+
+impl Checker {
+    fn check(&mut self, table: ReqTable, aspect: &str, required: i32, actual: i32) {
+        if satisfies(required, actual) {
+            self.any_met |= required > 0;
+        } else {
+            self.failures.push(Failure::Requirement {
+                table,
+                aspect: aspect.to_owned(),
+                required,
+                actual,
+            });
+        }
+    }
+}
+
+fn lookup(aspects: &Aspects, aspect: &str) -> i32 {
+    aspects.get(aspect).copied().unwrap_or(0)
+}
+
+fn eval_requirement(
+    aspect: &str,
+    required: &StringOrI32,
+    context: &SituationContext,
+) -> Result<i32, Failure> {
+    let expr = required.expr().map_err(|error| Failure::Parse {
+        aspect: aspect.to_owned(),
+        error,
+    })?;
+    expr.eval(context).map_err(|error| Failure::Eval {
+        aspect: aspect.to_owned(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compendium::SourceFile, data::Data};
+    use serde_json::json;
+
+    fn compendium(data: serde_json::Value) -> Compendium {
+        let mut compendium = Compendium::new();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "test.json".into(),
+        };
+        compendium.insert_data(&source, serde_json::from_value::<Data>(data).unwrap());
+        compendium
+    }
+
+    fn eligible(compendium: &Compendium, situation: &Situation) -> Vec<String> {
+        (simulate(compendium, situation).eligible.into_iter())
+            .map(|recipe| recipe.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn ngreq_is_negated_greq() {
+        let compendium = compendium(json!({
+            "recipes": [
+                { "id": "once", "actionid": "talk", "craftable": true, "ngreq": { "met": 1 } },
+                { "id": "twice", "actionid": "talk", "craftable": true, "ngreq": { "met": 2 } },
+                { "id": "negative", "actionid": "talk", "craftable": true, "ngreq": { "met": -2 } },
+            ],
+        }));
+        let mut situation = Situation::new("talk");
+        assert_eq!(
+            eligible(&compendium, &situation),
+            ["negative", "once", "twice"]
+        );
+        situation.global.insert("met".to_owned(), 1);
+        assert_eq!(eligible(&compendium, &situation), ["negative", "twice"]);
+        situation.global.insert("met".to_owned(), 2);
+        assert!(eligible(&compendium, &situation).is_empty());
+    }
+
+    #[test]
+    fn aspect_totals_saturate() {
+        let compendium = compendium(json!({
+            "elements": [{ "id": "big", "aspects": { "edge": 2147483647u32 } }],
+            "verbs": [{ "id": "talk", "aspects": { "edge": 4294967295u32 } }],
+        }));
+        let mut situation = Situation::new("talk");
+        situation.slotted.insert("big".to_owned(), 3);
+        let aspects = situation.aspects(&compendium);
+        assert_eq!(aspects["edge"], i32::MAX);
+        assert_eq!(aspects["big"], 3);
+        assert!(satisfies(i32::MIN, i32::MAX - 1));
+    }
+
+    fn ids(recipes: &[&Sourced<Recipes>]) -> Vec<String> {
+        recipes.iter().map(|recipe| recipe.id.clone()).collect()
+    }
+
+    fn failures(simulation: &Simulation, recipe: &str) -> Vec<String> {
+        let near_miss = (simulation.near_misses.iter())
+            .find(|near_miss| near_miss.recipe.id == recipe)
+            .unwrap_or_else(|| panic!("{recipe} is no near miss"));
+        (near_miss.failures.iter())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn study(slotted: &[(&str, u32)]) -> Situation {
+        let mut situation = Situation::new("study");
+        for &(id, count) in slotted {
+            situation.slotted.insert(id.to_owned(), count);
+        }
+        situation
+    }
+
+    #[test]
+    fn eligible_and_hints() {
+        let compendium = compendium(json!({
+            "elements": [{ "id": "book", "aspects": { "lore": 2 } }],
+            "recipes": [
+                { "id": "read", "actionid": "study", "craftable": true, "reqs": { "lore": 2 } },
+                { "id": "hint", "actionid": "study", "hintonly": true, "reqs": { "lore": 1 } },
+                { "id": "internal", "actionid": "study", "reqs": { "lore": 1 } },
+                { "id": "work", "actionid": "work", "craftable": true, "reqs": { "lore": 1 } },
+                {
+                    "id": "deep",
+                    "actionid": "study",
+                    "hintonly": true,
+                    "reqs": { "book": 1, "lore": 3 },
+                },
+            ],
+        }));
+        let simulation = simulate(&compendium, &study(&[("book", 1)]));
+        assert_eq!(ids(&simulation.eligible), ["read"]);
+        assert_eq!(ids(&simulation.hints), ["hint"]);
+        // A hint that fails is still a near miss, recipes that aren't craftable never are
+        assert_eq!(
+            failures(&simulation, "deep"),
+            ["requirements: lore needs 3, has 2"]
+        );
+    }
+
+    #[test]
+    fn near_misses() {
+        let compendium = compendium(json!({
+            "elements": [{ "id": "book", "aspects": { "lore": 2 } }],
+            "recipes": [
+                {
+                    "id": "close",
+                    "actionid": "study",
+                    "craftable": true,
+                    "reqs": { "lore": 2, "edge": 1, "heart": -1 },
+                    "tablereqs": { "candle": 1 },
+                },
+                { "id": "far", "actionid": "study", "craftable": true, "reqs": { "edge": 1 } },
+                // Only meeting a negative requirement doesn't count
+                {
+                    "id": "absent",
+                    "actionid": "study",
+                    "craftable": true,
+                    "reqs": { "edge": 1, "heart": -1 },
+                },
+            ],
+        }));
+        let simulation = simulate(&compendium, &study(&[("book", 1)]));
+        assert!(simulation.eligible.is_empty());
+        assert_eq!(simulation.near_misses.len(), 1);
+        assert_eq!(
+            failures(&simulation, "close"),
+            [
+                "requirements: edge needs 1, has 0",
+                "tablereqs: candle needs 1, has 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn wildcard_action_id() {
+        assert!(matches_verb("explore*", "explore.library"));
+        assert!(matches_verb("explore*", "explore"));
+        assert!(!matches_verb("explore*", "study"));
+        assert!(!matches_verb("explore", "explore.library"));
+
+        let compendium = compendium(json!({
+            "recipes": [
+                { "id": "any", "actionid": "explore*", "craftable": true },
+                { "id": "exact", "actionid": "explore", "craftable": true },
+            ],
+        }));
+        let simulation = simulate(&compendium, &Situation::new("explore.library"));
+        assert_eq!(ids(&simulation.eligible), ["any"]);
+    }
+
+    #[test]
+    fn less_than_requirements() {
+        assert!(satisfies(-1, 0));
+        assert!(!satisfies(-1, 1));
+        assert!(satisfies(-3, 2));
+        assert!(!satisfies(-3, 3));
+
+        let compendium = compendium(json!({
+            "elements": [{ "id": "book", "aspects": { "lore": 1 } }],
+            "recipes": [{
+                "id": "read",
+                "actionid": "study",
+                "craftable": true,
+                "reqs": { "book": 1, "lore": -3 },
+            }],
+        }));
+        assert_eq!(eligible(&compendium, &study(&[("book", 2)])), ["read"]);
+        let simulation = simulate(&compendium, &study(&[("book", 3)]));
+        assert_eq!(
+            failures(&simulation, "read"),
+            ["requirements: lore needs less than 3, has 3"]
+        );
+    }
+
+    #[test]
+    fn expression_requirements() {
+        let compendium = compendium(json!({
+            "elements": [{ "id": "book", "aspects": { "lore": 4, "edge": 2 } }],
+            "recipes": [
+                {
+                    "id": "double",
+                    "actionid": "study",
+                    "craftable": true,
+                    "reqs": { "book": 1, "lore": "edge * 2" },
+                },
+                {
+                    "id": "outside",
+                    "actionid": "study",
+                    "craftable": true,
+                    "reqs": { "book": 1, "lore": "[~/exterior:moth] + 1" },
+                },
+                {
+                    "id": "parse",
+                    "actionid": "study",
+                    "craftable": true,
+                    "reqs": { "book": 1, "lore": "edge +" },
+                },
+                {
+                    "id": "eval",
+                    "actionid": "study",
+                    "craftable": true,
+                    "reqs": { "book": 1, "lore": "lore / (edge - 2)" },
+                },
+            ],
+        }));
+        let mut situation = study(&[("book", 1)]);
+        situation.paths.insert(
+            "~/exterior".to_owned(),
+            Aspects::from([("moth".to_owned(), 3)]),
+        );
+        let simulation = simulate(&compendium, &situation);
+        assert_eq!(ids(&simulation.eligible), ["double", "outside"]);
+
+        let [Failure::Parse { aspect, .. }] = &simulation.near_misses[1].failures[..] else {
+            panic!("{:?}", simulation.near_misses);
+        };
+        assert_eq!(simulation.near_misses[1].recipe.id, "parse");
+        assert_eq!(aspect, "lore");
+        assert!(matches!(
+            simulation.near_misses[0].failures[..],
+            [Failure::Eval {
+                error: EvalError::DivisionByZero,
+                ..
+            }]
+        ));
+
+        situation.paths.clear();
+        let simulation = simulate(&compendium, &situation);
+        assert!(matches!(
+            failures(&simulation, "outside")[..],
+            [ref failure] if failure.starts_with("requirements: lore: ")
+        ));
+    }
+}
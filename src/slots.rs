@@ -0,0 +1,294 @@
+//! One view over the slots of elements, recipes and verbs.
+//!
+//! An element fits a slot if it has none of the `forbidden` aspects, all of the `essential`
+//! aspects and (if there are any) at least one of the `required` aspects, each in at least the
+//! amount given.
+//!
+//! A slot of an element with `ifaspectspresent` is only shown while the aspects of its situation
+//! contain all of them, see [`Slot::is_shown`]. Whether an element fits doesn't depend on that.
+
+use crate::{
+    compendium::{Compendium, Sourced},
+    data::{Elements, ElementsSlots, EntityKind, RecipesPreslots, RecipesSlots, VerbsSlot},
+    simulate::{element_aspects, Aspects},
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Slot {
+    pub id: String,
+    pub label: Option<String>,
+    pub required: Aspects,
+    pub essential: Aspects,
+    pub forbidden: Aspects,
+    /// The aspects the situation needs for the slot to be shown, only for [`ElementsSlots`].
+    pub if_aspects_present: Aspects,
+    /// The verb this slot is shown on, only for [`ElementsSlots`] and [`RecipesSlots`].
+    pub action_id: Option<String>,
+    /// Whether the slotted element is consumed when the recipe completes.
+    pub consumes: bool,
+    /// Whether the slot grabs a fitting element by itself, from [`Self::from_path`].
+    pub greedy: bool,
+    pub from_path: Option<String>,
+}
+
+fn to_aspects<V: Copy + Into<i64>>(map: &BTreeMap<String, V>) -> Aspects {
+    map.iter()
+        .map(|(aspect, &value)| {
+            (
+                aspect.clone(),
+                value.into().clamp(0, i32::MAX as i64) as i32,
+            )
+        })
+        .collect()
+}
+
+fn opt_to_aspects<V: Copy + Into<i64>>(map: &Option<BTreeMap<String, V>>) -> Aspects {
+    map.as_ref().map(to_aspects).unwrap_or_default()
+}
+
+impl From<&ElementsSlots> for Slot {
+    fn from(slot: &ElementsSlots) -> Self {
+        Self {
+            id: slot.id.clone(),
+            label: slot.label.clone(),
+            required: to_aspects(&slot.required),
+            essential: to_aspects(&slot.essential),
+            forbidden: to_aspects(&slot.forbidden),
+            if_aspects_present: opt_to_aspects(&slot.if_aspects_present),
+            action_id: Some(slot.action_id.clone()),
+            consumes: slot.consumes,
+            greedy: false,
+            from_path: None,
+        }
+    }
+}
+
+impl From<&RecipesSlots> for Slot {
+    fn from(slot: &RecipesSlots) -> Self {
+        Self {
+            id: slot.id.clone(),
+            label: slot.label.clone(),
+            required: opt_to_aspects(&slot.required),
+            essential: opt_to_aspects(&slot.essential),
+            forbidden: opt_to_aspects(&slot.forbidden),
+            action_id: slot.actionid.clone(),
+            consumes: slot.consumes,
+            greedy: slot.greedy,
+            if_aspects_present: Aspects::new(),
+            from_path: slot.frompath.clone(),
+        }
+    }
+}
+
+impl From<&RecipesPreslots> for Slot {
+    fn from(slot: &RecipesPreslots) -> Self {
+        Self {
+            id: slot.id.clone(),
+            label: Some(slot.label.clone()),
+            required: opt_to_aspects(&slot.required),
+            essential: opt_to_aspects(&slot.essential),
+            forbidden: opt_to_aspects(&slot.forbidden),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<&VerbsSlot> for Slot {
+    fn from(slot: &VerbsSlot) -> Self {
+        Self {
+            id: slot.id.clone(),
+            label: slot.label.clone(),
+            required: opt_to_aspects(&slot.required),
+            essential: opt_to_aspects(&slot.essential),
+            forbidden: opt_to_aspects(&slot.forbidden),
+            ..Self::default()
+        }
+    }
+}
+
+/// Why an element doesn't fit a slot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum SlotMismatch {
+    Forbidden {
+        aspect: String,
+        forbidden: i32,
+        actual: i32,
+    },
+    MissingEssential {
+        aspect: String,
+        essential: i32,
+        actual: i32,
+    },
+    /// None of the `required` aspects is present in the required amount.
+    NoRequired,
+}
+
+impl Display for SlotMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlotMismatch::Forbidden {
+                aspect,
+                forbidden,
+                actual,
+            } => write!(
+                f,
+                "has {actual} {aspect}, which is forbidden from {forbidden}"
+            ),
+            SlotMismatch::MissingEssential {
+                aspect,
+                essential,
+                actual,
+            } => write!(f, "has {actual} {aspect}, but {essential} are essential"),
+            SlotMismatch::NoRequired => write!(f, "has none of the required aspects"),
+        }
+    }
+}
+
+impl Slot {
+    /// Whether the slot is shown in a situation with `aspects`, which is always the case unless
+    /// it has [`Self::if_aspects_present`].
+    pub fn is_shown(&self, aspects: &Aspects) -> bool {
+        (self.if_aspects_present.iter())
+            .all(|(aspect, &value)| aspects.get(aspect).copied().unwrap_or(0) >= value.max(1))
+    }
+
+    /// Every reason why an element with `aspects` doesn't fit, empty if it does.
+    pub fn check(&self, aspects: &Aspects) -> Vec<SlotMismatch> {
+        let actual = |aspect: &str| aspects.get(aspect).copied().unwrap_or(0);
+        let mut mismatches = Vec::new();
+        for (aspect, &forbidden) in &self.forbidden {
+            let actual = actual(aspect);
+            if actual >= forbidden.max(1) {
+                mismatches.push(SlotMismatch::Forbidden {
+                    aspect: aspect.clone(),
+                    forbidden,
+                    actual,
+                });
+            }
+        }
+        for (aspect, &essential) in &self.essential {
+            let actual = actual(aspect);
+            if actual < essential.max(1) {
+                mismatches.push(SlotMismatch::MissingEssential {
+                    aspect: aspect.clone(),
+                    essential,
+                    actual,
+                });
+            }
+        }
+        if !self.required.is_empty()
+            && !self
+                .required
+                .iter()
+                .any(|(aspect, &required)| actual(aspect) >= required.max(1))
+        {
+            mismatches.push(SlotMismatch::NoRequired);
+        }
+        mismatches
+    }
+
+    pub fn fits(&self, aspects: &Aspects) -> bool {
+        self.check(aspects).is_empty()
+    }
+
+    /// Every element (that isn't an aspect) that fits this slot.
+    pub fn fitting_elements<'a>(&self, compendium: &'a Compendium) -> Vec<&'a Sourced<Elements>> {
+        compendium
+            .elements
+            .values()
+            .filter(|element| {
+                element.is_aspect != Some(true) && self.fits(&element_aspects(element))
+            })
+            .collect()
+    }
+}
+
+/// The slots of the entity `kind[id]`, [`None`] if there is no such entity with slots.
+///
+/// For recipes these are the `preslots` followed by the `slots`.
+pub fn entity_slots(compendium: &Compendium, kind: EntityKind, id: &str) -> Option<Vec<Slot>> {
+    match kind {
+        EntityKind::Elements => {
+            let element = compendium.elements.get(id)?;
+            Some(element.slots.iter().map(Slot::from).collect())
+        }
+        EntityKind::Recipes => {
+            let recipe = compendium.recipes.get(id)?;
+            let preslots = recipe.preslots.iter().flatten().map(Slot::from);
+            let slots = recipe.slots.iter().flatten().map(Slot::from);
+            Some(preslots.chain(slots).collect())
+        }
+        EntityKind::Verbs => {
+            let verb = compendium.verbs.get(id)?;
+            let slot = verb.slot.iter().map(Slot::from);
+            let slots = verb.slots.iter().flatten().map(Slot::from);
+            Some(slot.chain(slots).collect())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn aspects<const N: usize>(aspects: [(&str, i32); N]) -> Aspects {
+        (aspects.into_iter())
+            .map(|(aspect, value)| (aspect.to_owned(), value))
+            .collect()
+    }
+
+    #[test]
+    fn conditional_slot() {
+        let slot: ElementsSlots = serde_json::from_value(json!({
+            "id": "memory",
+            "actionid": "talk",
+            "required": { "memory": 1 },
+            "ifaspectspresent": { "soul": 2 },
+        }))
+        .unwrap();
+        let slot = Slot::from(&slot);
+
+        assert!(!slot.is_shown(&aspects([])));
+        assert!(!slot.is_shown(&aspects([("soul", 1)])));
+        assert!(slot.is_shown(&aspects([("soul", 2)])));
+
+        assert!(slot.fits(&aspects([("memory", 1)])));
+        assert_eq!(
+            slot.check(&aspects([("soul", 2)])),
+            [SlotMismatch::NoRequired]
+        );
+    }
+
+    #[test]
+    fn unconditional_slot() {
+        let slot = Slot {
+            forbidden: aspects([("rust", 1)]),
+            essential: aspects([("edge", 2)]),
+            ..Slot::default()
+        };
+        assert!(slot.is_shown(&aspects([])));
+        assert!(slot.fits(&aspects([("edge", 2)])));
+        assert_eq!(
+            slot.check(&aspects([("edge", 1), ("rust", 1)])),
+            [
+                SlotMismatch::Forbidden {
+                    aspect: "rust".to_owned(),
+                    forbidden: 1,
+                    actual: 1,
+                },
+                SlotMismatch::MissingEssential {
+                    aspect: "edge".to_owned(),
+                    essential: 2,
+                    actual: 1,
+                },
+            ]
+        );
+    }
+}
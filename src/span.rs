@@ -0,0 +1,432 @@
+//! Where the top-level entities of a content file are.
+//!
+//! Spans are found by a small scanner over the decoded text, independent of deserialization, so
+//! offsets are in bytes of the UTF-8 text (which differ from the file for other encodings).
+
+use crate::{compendium::SourceFile, data::EntityKind};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+/// A range of bytes in a content file, with the (1-based) line and column it starts at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The span of every top-level entity of a file, in the order they appear in.
+pub type Spans = BTreeMap<EntityKind, Vec<Span>>;
+
+/// A [`SourceFile`] with an optional [`Span`], displayed as `path:line:column`.
+#[derive(Debug, Clone, Copy)]
+pub struct Location<'a>(pub &'a SourceFile, pub Option<Span>);
+
+impl Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Some(span) => write!(f, "{}:{span}", self.0),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// The start of every line, to turn byte offsets into lines and columns.
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { starts }
+    }
+
+    /// The 1-based line and column (in bytes) of `offset`.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        (line, offset - self.starts[line - 1] + 1)
+    }
+
+    /// The byte offset of the 1-based `line` and 0-based byte `column`.
+    pub fn offset(&self, line: usize, column: usize) -> usize {
+        let start = self.starts.get(line.saturating_sub(1)).copied();
+        start.unwrap_or(usize::MAX).saturating_add(column)
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let (line, column) = self.position(start);
+        let (end_line, _) = self.position(end.saturating_sub(1).max(start));
+        Span {
+            start,
+            end,
+            line,
+            column,
+            end_line,
+        }
+    }
+}
+
+/// Find the span of every element of the top-level entity arrays in `text`.
+///
+/// Scanning stops at the first thing that isn't JSON, everything found up to that point is kept.
+pub fn entity_spans(text: &str) -> Spans {
+    let lines = LineIndex::new(text);
+    let mut scanner = Scanner {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let mut spans = Spans::new();
+
+    scanner.skip_whitespace();
+    if !scanner.eat(b'{') {
+        return spans;
+    }
+    loop {
+        scanner.skip_whitespace();
+        if scanner.eat(b'}') {
+            break;
+        }
+        let Some(key) = scanner.string() else {
+            break;
+        };
+        scanner.skip_whitespace();
+        if !scanner.eat(b':') {
+            break;
+        }
+        scanner.skip_whitespace();
+
+        match key.parse::<EntityKind>().ok() {
+            Some(kind) if scanner.eat(b'[') => {
+                // A repeated key replaces the earlier one, like when deserializing
+                let entities = spans.entry(kind).or_default();
+                entities.clear();
+                loop {
+                    scanner.skip_whitespace();
+                    if scanner.eat(b']') {
+                        break;
+                    }
+                    let start = scanner.pos;
+                    if !scanner.value() {
+                        return spans;
+                    }
+                    entities.push(lines.span(start, scanner.pos));
+                    scanner.skip_whitespace();
+                    scanner.eat(b',');
+                }
+            }
+            _ => {
+                if !scanner.value() {
+                    break;
+                }
+            }
+        }
+        scanner.skip_whitespace();
+        scanner.eat(b',');
+    }
+    spans
+}
+
+/// A key or index of a path into a JSON document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Format `path` like `elements[3].slots[0].required`.
+pub fn format_path(path: &[PathSegment]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) if out.is_empty() => out.push_str(key),
+            PathSegment::Key(key) => {
+                out.push('.');
+                out.push_str(key);
+            }
+            PathSegment::Index(index) => out.push_str(&format!("[{index}]")),
+        }
+    }
+    out
+}
+
+/// The path of the value at byte `offset` of `text`, and the byte range of the string or scalar
+/// token around `offset` (if there is one).
+///
+/// Like [`entity_spans`], this only looks at the structure and doesn't validate anything.
+pub fn path_at(text: &str, offset: usize) -> (Vec<PathSegment>, Option<(usize, usize)>) {
+    enum Frame {
+        Object(Option<String>),
+        Array(usize),
+    }
+
+    let mut scanner = Scanner {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let mut stack = Vec::new();
+    let mut token = None;
+    loop {
+        scanner.skip_whitespace();
+        let start = scanner.pos;
+        if start >= offset {
+            break;
+        }
+        match scanner.peek() {
+            None => break,
+            Some(b'{') => stack.push(Frame::Object(None)),
+            Some(b'[') => stack.push(Frame::Array(0)),
+            Some(b'}' | b']') => {
+                stack.pop();
+            }
+            Some(b',') => match stack.last_mut() {
+                Some(Frame::Object(key)) => *key = None,
+                Some(Frame::Array(index)) => *index += 1,
+                None => (),
+            },
+            Some(b':') => (),
+            Some(b'"') => {
+                let string = scanner.string();
+                token = Some((start, scanner.pos));
+                if let (Some(Frame::Object(key @ None)), Some(string)) = (stack.last_mut(), string)
+                {
+                    *key = Some(string.to_owned());
+                }
+                continue;
+            }
+            Some(_) => {
+                scanner.scalar();
+                token = Some((start, scanner.pos));
+                continue;
+            }
+        }
+        scanner.pos += 1;
+    }
+
+    let path = stack
+        .into_iter()
+        .filter_map(|frame| match frame {
+            Frame::Object(key) => key.map(PathSegment::Key),
+            Frame::Array(index) => Some(PathSegment::Index(index)),
+        })
+        .collect();
+    let token = token.filter(|&(start, end)| start <= offset && offset < end);
+    (path, token)
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matches = self.peek() == Some(byte);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    /// Skip whitespace as well as `//` and `/* */` comments.
+    fn skip_whitespace(&mut self) {
+        loop {
+            match (self.peek(), self.bytes.get(self.pos + 1)) {
+                (Some(byte), _) if byte.is_ascii_whitespace() => self.pos += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    while self.peek().is_some_and(|byte| byte != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.pos += 2;
+                    while self.pos < self.bytes.len() && !self.bytes[self.pos..].starts_with(b"*/")
+                    {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.bytes.len());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Skip a string and return its contents, with escapes left as they are.
+    fn string(&mut self) -> Option<&'a str> {
+        if !self.eat(b'"') {
+            return None;
+        }
+        let start = self.pos;
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+        let end = self.pos.min(self.bytes.len());
+        self.pos += 1;
+        std::str::from_utf8(&self.bytes[start..end]).ok()
+    }
+
+    /// Skip a single value, returns whether there was one.
+    fn value(&mut self) -> bool {
+        let start = self.pos;
+        let mut depth = 0_usize;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return false,
+                Some(b'"') => {
+                    if self.string().is_none() {
+                        return false;
+                    }
+                }
+                Some(b'{' | b'[') => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                Some(b'}' | b']') if depth > 0 => {
+                    depth -= 1;
+                    self.pos += 1;
+                }
+                Some(b'}' | b']') => break,
+                Some(b',' | b':') if depth > 0 => self.pos += 1,
+                Some(b',' | b':') => break,
+                Some(_) => self.scalar(),
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+        self.pos > start
+    }
+
+    /// Skip a number, `true`, `false` or `null`.
+    fn scalar(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|byte| !byte.is_ascii_whitespace() && !b",:[]{}\"".contains(&byte))
+        {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of every span of `kind`.
+    fn texts<'a>(text: &'a str, spans: &Spans, kind: EntityKind) -> Vec<&'a str> {
+        (spans.get(&kind).into_iter().flatten())
+            .map(|span| &text[span.start..span.end])
+            .collect()
+    }
+
+    #[test]
+    fn strings_with_brackets_and_quotes() {
+        let text = r#"{ "elements": [
+  { "id": "a", "label": "a { or [ \" in a string" },
+  { "id": "b", "desc": "]}\\" }
+] }"#;
+        let spans = entity_spans(text);
+        assert_eq!(
+            texts(text, &spans, EntityKind::Elements),
+            [
+                r#"{ "id": "a", "label": "a { or [ \" in a string" }"#,
+                r#"{ "id": "b", "desc": "]}\\" }"#,
+            ]
+        );
+        let b = spans[&EntityKind::Elements][1];
+        assert_eq!((b.line, b.column, b.end_line), (3, 3, 3));
+    }
+
+    #[test]
+    fn trailing_commas_and_comments() {
+        let text = r#"{
+  // comment
+  "recipes": [
+    { "id": "a", },
+    /* b */ { "id": "b" },
+  ],
+}"#;
+        let spans = entity_spans(text);
+        assert_eq!(
+            texts(text, &spans, EntityKind::Recipes),
+            [r#"{ "id": "a", }"#, r#"{ "id": "b" }"#]
+        );
+        assert_eq!(spans[&EntityKind::Recipes][1].line, 5);
+    }
+
+    #[test]
+    fn multiple_kinds() {
+        let text = r#"{
+  "elements": [{ "id": "a" }, { "id": "b" }],
+  "comments": { "recipes": [{ "id": "not an entity" }] },
+  "recipes": [
+    {
+      "id": "c"
+    }
+  ]
+}"#;
+        let spans = entity_spans(text);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            texts(text, &spans, EntityKind::Elements),
+            [r#"{ "id": "a" }"#, r#"{ "id": "b" }"#]
+        );
+        let c = spans[&EntityKind::Recipes][0];
+        assert_eq!((c.line, c.column, c.end_line), (5, 5, 7));
+    }
+
+    #[test]
+    fn stops_at_invalid_json() {
+        let text = r#"{ "elements": [{ "id": "a" }, { "id": "b" "#;
+        let spans = entity_spans(text);
+        assert_eq!(
+            texts(text, &spans, EntityKind::Elements),
+            [r#"{ "id": "a" }"#]
+        );
+    }
+
+    #[test]
+    fn path_at_offset() {
+        let text = r#"{
+  "elements": [
+    { "id": "a" },
+    { "id": "b", "slots": [{ "label": "x [ \" y", "n": 12 }] }
+  ]
+}"#;
+        let at = |needle: &str| {
+            // Inside of the token, like the positions of serde_json errors
+            let offset = text.find(needle).unwrap() + 1;
+            let (path, token) = path_at(text, offset);
+            (
+                format_path(&path),
+                token.map(|(start, end)| &text[start..end]),
+            )
+        };
+        assert_eq!(at("\"a\""), ("elements[0].id".to_owned(), Some("\"a\"")));
+        assert_eq!(at("\"x [").0, "elements[1].slots[0].label");
+        assert_eq!(at("12"), ("elements[1].slots[0].n".to_owned(), Some("12")));
+    }
+}
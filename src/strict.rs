@@ -0,0 +1,224 @@
+//! Find the extensions to standard JSON a content file relies on.
+//!
+//! The game's JSON parser is lenient, and our `serde_json` fork follows it with trailing commas
+//! and extended strings. A file that uses none of these loads with any standard JSON parser.
+//!
+//! Comments aren't an extension, since the fork rejects them as well. They are still skipped when
+//! looking for extensions, so a file that fails to parse because of one is reported the same.
+
+use crate::span::{LineIndex, Span};
+use serde::Serialize;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Extension {
+    /// A `,` after the last item of an array or object.
+    TrailingComma,
+    /// A control character, like a line break or tab, that isn't escaped inside a string.
+    ControlCharacter,
+    /// An escape sequence standard JSON doesn't have, like `\'`.
+    InvalidEscape,
+}
+
+impl Extension {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Extension::TrailingComma => "trailing comma",
+            Extension::ControlCharacter => "unescaped control character",
+            Extension::InvalidEscape => "invalid escape",
+        }
+    }
+}
+
+impl Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single place an [`Extension`] is used at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ExtensionUse {
+    pub extension: Extension,
+    pub span: Span,
+}
+
+impl Display for ExtensionUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.extension, self.span)
+    }
+}
+
+/// Find every use of an [`Extension`] in `text`, in the order they appear in.
+///
+/// This only looks at the tokens, so it works on files that don't parse as well.
+pub fn find_extensions(text: &str) -> Vec<ExtensionUse> {
+    let lines = LineIndex::new(text);
+    let bytes = text.as_bytes();
+    let mut uses = Vec::new();
+    let mut push = |extension, start, end| {
+        uses.push(ExtensionUse {
+            extension,
+            span: lines.span(start, end),
+        })
+    };
+
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        match bytes[pos] {
+            b'"' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    match bytes[pos] {
+                        b'\\' => {
+                            let escape = bytes.get(pos + 1).copied();
+                            if !escape.is_some_and(|byte| b"\"\\/bfnrtu".contains(&byte)) {
+                                push(Extension::InvalidEscape, pos, (pos + 2).min(bytes.len()));
+                            }
+                            pos += 2;
+                        }
+                        byte if byte < 0x20 => {
+                            push(Extension::ControlCharacter, pos, pos + 1);
+                            pos += 1;
+                        }
+                        _ => pos += 1,
+                    }
+                }
+                pos += 1;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'/') || bytes.get(pos + 1) == Some(&b'*') => {
+                pos = skip_comment(bytes, pos);
+            }
+            b',' => {
+                pos += 1;
+                let mut next = pos;
+                loop {
+                    while next < bytes.len() && bytes[next].is_ascii_whitespace() {
+                        next += 1;
+                    }
+                    match skip_comment(bytes, next) {
+                        end if end > next => next = end,
+                        _ => break,
+                    }
+                }
+                if matches!(bytes.get(next), Some(b']' | b'}')) {
+                    push(Extension::TrailingComma, start, pos);
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    uses
+}
+
+/// The end of the comment starting at `pos`, or `pos` if there isn't one.
+fn skip_comment(bytes: &[u8], pos: usize) -> usize {
+    match bytes.get(pos..pos + 2) {
+        Some(b"//") => (pos..bytes.len())
+            .find(|&i| bytes[i] == b'\n')
+            .unwrap_or(bytes.len()),
+        Some(b"/*") => (pos + 2..bytes.len().saturating_sub(1))
+            .find(|&i| &bytes[i..i + 2] == b"*/")
+            .map_or(bytes.len(), |i| i + 2),
+        _ => pos,
+    }
+}
+
+/// A file uses extensions to standard JSON, but was read in strict mode.
+#[derive(Debug, Clone)]
+pub struct NonStandardJson {
+    pub uses: Vec<ExtensionUse>,
+}
+
+impl Display for NonStandardJson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not standard JSON: ")?;
+        for (i, extension_use) in self.uses.iter().take(3).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{extension_use}")?;
+        }
+        if self.uses.len() > 3 {
+            write!(f, " and {} more", self.uses.len() - 3)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for NonStandardJson {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The extensions of `text` with the text they span.
+    fn extensions(text: &str) -> Vec<(Extension, &str)> {
+        (find_extensions(text).into_iter())
+            .map(|found| (found.extension, &text[found.span.start..found.span.end]))
+            .collect()
+    }
+
+    #[test]
+    fn standard() {
+        let text = r#"{ "a": [1, 2], "b": "\"\\\/\b\f\n\r\t\u00e9", "c": {} }"#;
+        assert_eq!(extensions(text), []);
+    }
+
+    #[test]
+    fn escapes() {
+        let text = r#"{ "a": "it\'s", "b": "\x", "c": "\\'" }"#;
+        assert_eq!(
+            extensions(text),
+            [
+                (Extension::InvalidEscape, r"\'"),
+                (Extension::InvalidEscape, r"\x"),
+            ]
+        );
+        // A backslash right before the end
+        assert_eq!(extensions("\"\\"), [(Extension::InvalidEscape, "\\")]);
+    }
+
+    #[test]
+    fn control_characters() {
+        let text = "{ \"a\": \"two\nlines\", \"b\": \"\ttab\" }\n";
+        let found = find_extensions(text);
+        assert_eq!(
+            (found.iter())
+                .map(|found| (found.extension, found.span.line, found.span.column))
+                .collect::<Vec<_>>(),
+            [
+                (Extension::ControlCharacter, 1, 12),
+                (Extension::ControlCharacter, 2, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_commas() {
+        let text = "{ \"a\": [1, 2,], \"b\": \"x,]\", }";
+        assert_eq!(
+            extensions(text),
+            [
+                (Extension::TrailingComma, ","),
+                (Extension::TrailingComma, ",")
+            ]
+        );
+        assert_eq!(find_extensions(text)[0].span.column, 13);
+    }
+
+    #[test]
+    fn trailing_commas_before_comments() {
+        let text = "[\n  1, // one\n  /* more, later */\n]";
+        assert_eq!(extensions(text), [(Extension::TrailingComma, ",")]);
+        assert_eq!(find_extensions(text)[0].span.line, 2);
+        // Not trailing, and the comment isn't reported either
+        assert_eq!(extensions("[1, /* , ] */ 2]"), []);
+        assert_eq!(extensions("[1, // ,]\n 2]"), []);
+    }
+}
@@ -0,0 +1,478 @@
+//! A deserializer over a [`Value`] that knows where in the value it is.
+//!
+//! [`Tracked`] deserializes like [`Value`] itself does, but keeps the path of the current value,
+//! and tells a [`Hook`] what the data model asks for at each path and where deserialization
+//! failed. [`crate::unknown`] builds on it to find unknown fields, [`crate::drift`] to record what
+//! the model expects.
+//!
+//! Every path is kept twice: as it is, like `slots[0].required.edge`, and normalized so it can be
+//! compared across entities, with array items as `[]` and the keys of maps the model indexes by id
+//! as `*`, like `slots[].required.*`.
+
+use crate::{drift::Expected, span::PathSegment};
+use serde::{
+    de::{
+        self, value::StringDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess,
+        Visitor,
+    },
+    forward_to_deserialize_any, Deserializer,
+};
+use serde_json::{Map, Value};
+
+/// Where a [`Tracked`] deserializer is.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct At<'a> {
+    pub path: &'a [PathSegment],
+    /// The normalized path, see the [module docs](self).
+    pub normalized: &'a str,
+}
+
+/// What a [`Tracked`] deserializer reports, everything defaults to doing nothing.
+pub(crate) trait Hook {
+    /// The model asks for `expected` at `at`.
+    fn expect(&self, at: At<'_>, expected: Expected) {
+        let _ = (at, expected);
+    }
+
+    /// The model asks for a struct with `fields` (and aliases) at `at`.
+    fn fields(&self, at: At<'_>, fields: &'static [&'static str]) {
+        let _ = (at, fields);
+    }
+
+    /// The model accepts `null` at `at`, since it is an [`Option`].
+    fn nullable(&self, at: At<'_>) {
+        let _ = at;
+    }
+
+    /// Deserializing `key` of the map at `at` failed, or the value at `at` itself if `key` is
+    /// [`None`].
+    ///
+    /// A failure is reported by every value it propagates through, innermost first.
+    fn failed(&self, at: At<'_>, key: Option<&str>) {
+        let _ = (at, key);
+    }
+}
+
+pub(crate) struct Tracked<'a, H: ?Sized> {
+    value: Value,
+    path: Vec<PathSegment>,
+    normalized: String,
+    hook: &'a H,
+}
+
+impl<'a, H: Hook + ?Sized> Tracked<'a, H> {
+    /// Deserialize `value` from its root.
+    pub fn new(value: Value, hook: &'a H) -> Self {
+        Self {
+            value,
+            path: Vec::new(),
+            normalized: String::new(),
+            hook,
+        }
+    }
+
+    fn at(&self) -> At<'_> {
+        At {
+            path: &self.path,
+            normalized: &self.normalized,
+        }
+    }
+
+    fn expect(&self, expected: Expected) {
+        self.hook.expect(self.at(), expected);
+    }
+
+    /// Run `f` on the value, reporting it to the hook if it fails.
+    fn forward<T>(
+        self,
+        f: impl FnOnce(Value, Parts<'a, H>) -> serde_json::Result<T>,
+    ) -> serde_json::Result<T> {
+        let Tracked {
+            value,
+            path,
+            normalized,
+            hook,
+        } = self;
+        let result = f(
+            value,
+            Parts {
+                path: path.clone(),
+                normalized: normalized.clone(),
+                hook,
+            },
+        );
+        if result.is_err() {
+            let at = At {
+                path: &path,
+                normalized: &normalized,
+            };
+            hook.failed(at, None);
+        }
+        result
+    }
+}
+
+/// The parts of a [`Tracked`] to access its children with.
+struct Parts<'a, H: ?Sized> {
+    path: Vec<PathSegment>,
+    normalized: String,
+    hook: &'a H,
+}
+
+impl<'a, H: ?Sized> Parts<'a, H> {
+    fn map(self, map: Map<String, Value>, wildcard: bool) -> TrackedMap<'a, H> {
+        TrackedMap {
+            iter: map.into_iter(),
+            value: None,
+            wildcard,
+            parts: self,
+        }
+    }
+
+    fn seq(self, seq: Vec<Value>) -> TrackedSeq<'a, H> {
+        TrackedSeq {
+            iter: seq.into_iter().enumerate(),
+            parts: self,
+        }
+    }
+}
+
+macro_rules! tracked_scalar {
+    ($($method:ident => $expected:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            self.expect(Expected::$expected);
+            self.forward(|value, _| value.$method(visitor))
+        }
+    )*};
+}
+
+impl<'de, H: Hook + ?Sized> Deserializer<'de> for Tracked<'_, H> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.expect(Expected::Any);
+        self.forward(|value, parts| match value {
+            Value::Object(map) => visitor.visit_map(parts.map(map, false)),
+            Value::Array(seq) => visitor.visit_seq(parts.seq(seq)),
+            value => value.deserialize_any(visitor),
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.expect(Expected::Struct);
+        self.hook.fields(self.at(), fields);
+        self.forward(|value, parts| match value {
+            Value::Object(map) => visitor.visit_map(parts.map(map, false)),
+            value => value.deserialize_struct(name, fields, visitor),
+        })
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.expect(Expected::Map);
+        self.forward(|value, parts| match value {
+            Value::Object(map) => visitor.visit_map(parts.map(map, true)),
+            value => value.deserialize_map(visitor),
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.expect(Expected::Seq);
+        self.forward(|value, parts| match value {
+            Value::Array(seq) => visitor.visit_seq(parts.seq(seq)),
+            value => value.deserialize_seq(visitor),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.hook.nullable(self.at());
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.expect(Expected::Enum);
+        self.forward(|value, _| value.deserialize_enum(name, variants, visitor))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.expect(Expected::Unit);
+        self.forward(|value, _| value.deserialize_unit_struct(name, visitor))
+    }
+
+    tracked_scalar! {
+        deserialize_bool => Bool,
+        deserialize_i8 => Number,
+        deserialize_i16 => Number,
+        deserialize_i32 => Number,
+        deserialize_i64 => Number,
+        deserialize_u8 => Number,
+        deserialize_u16 => Number,
+        deserialize_u32 => Number,
+        deserialize_u64 => Number,
+        deserialize_f32 => Number,
+        deserialize_f64 => Number,
+        deserialize_char => String,
+        deserialize_str => String,
+        deserialize_string => String,
+        deserialize_identifier => String,
+        deserialize_bytes => Any,
+        deserialize_byte_buf => Any,
+        deserialize_unit => Unit,
+        deserialize_ignored_any => Any,
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128
+    }
+}
+
+struct TrackedMap<'a, H: ?Sized> {
+    iter: serde_json::map::IntoIter,
+    value: Option<(String, Value)>,
+    /// Whether the keys are ids, which are normalized to `*`.
+    wildcard: bool,
+    parts: Parts<'a, H>,
+}
+
+impl<'de, H: Hook + ?Sized> MapAccess<'de> for TrackedMap<'_, H> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        match seed.deserialize(MapKey(key.clone())) {
+            Ok(k) => {
+                self.value = Some((key, value));
+                Ok(Some(k))
+            }
+            Err(err) => {
+                let at = At {
+                    path: &self.parts.path,
+                    normalized: &self.parts.normalized,
+                };
+                self.parts.hook.failed(at, Some(&key));
+                Err(err)
+            }
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        let normalized = join(
+            &self.parts.normalized,
+            if self.wildcard { "*" } else { &key },
+        );
+        let mut path = self.parts.path.clone();
+        path.push(PathSegment::Key(key));
+        seed.deserialize(Tracked {
+            value,
+            path,
+            normalized,
+            hook: self.parts.hook,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct TrackedSeq<'a, H: ?Sized> {
+    iter: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+    parts: Parts<'a, H>,
+}
+
+impl<'de, H: Hook + ?Sized> SeqAccess<'de> for TrackedSeq<'_, H> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        let Some((index, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        let mut path = self.parts.path.clone();
+        path.push(PathSegment::Index(index));
+        seed.deserialize(Tracked {
+            value,
+            path,
+            normalized: format!("{}[]", self.parts.normalized),
+            hook: self.parts.hook,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Join a normalized path and a key.
+pub(crate) fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_owned(),
+        false => format!("{path}.{key}"),
+    }
+}
+
+/// A map key, which can also be deserialized as a number like serde_json does.
+struct MapKey(String);
+
+macro_rules! parse_key {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self.0.parse() {
+                Ok(n) => visitor.$visit(n),
+                Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&self.0), &visitor)),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for MapKey {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let de: StringDeserializer<serde_json::Error> = self.0.into_deserializer();
+        de.deserialize_any(visitor)
+    }
+
+    parse_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::Elements, drift::DriftReport, span::format_path, unknown::from_value_lenient,
+    };
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct Failures(RefCell<Vec<(String, String, Option<String>)>>);
+
+    impl Hook for Failures {
+        fn failed(&self, at: At<'_>, key: Option<&str>) {
+            self.0.borrow_mut().push((
+                format_path(at.path),
+                at.normalized.to_owned(),
+                key.map(ToOwned::to_owned),
+            ));
+        }
+    }
+
+    #[test]
+    fn failure_paths() {
+        let entity = json!({
+            "id": "hammer",
+            "slots": [
+                { "id": "a", "actionid": "work" },
+                { "id": "b", "actionid": "work", "required": { "edge": "x" } },
+            ],
+        });
+        let failures = Failures::default();
+        assert!(Elements::deserialize(Tracked::new(entity, &failures)).is_err());
+        let failures = failures.0.into_inner();
+        assert_eq!(
+            failures[0],
+            (
+                "slots[1].required.edge".to_owned(),
+                "slots[].required.*".to_owned(),
+                None
+            )
+        );
+        assert_eq!(failures.last().unwrap().0, "");
+    }
+
+    #[test]
+    fn unknown_key_failure() {
+        let entity = json!({ "id": "hammer", "slots": [{ "id": "a", "actionid": "w", "x": 1 }] });
+        let failures = Failures::default();
+        assert!(Elements::deserialize(Tracked::new(entity.clone(), &failures)).is_err());
+        let failures = failures.0.into_inner();
+        assert_eq!(
+            failures[0],
+            (
+                "slots[0]".to_owned(),
+                "slots[]".to_owned(),
+                Some("x".to_owned())
+            )
+        );
+
+        // Both users of the tracker agree on where it is
+        let (_, extra) = from_value_lenient::<Elements>(entity.clone()).unwrap();
+        assert_eq!(extra.keys().collect::<Vec<_>>(), ["slots[0].x"]);
+        let report =
+            DriftReport::build([json!({ "elements": [entity] }).as_object().unwrap().clone()]);
+        let key = &report.kinds[&crate::data::EntityKind::Elements].keys["slots[].x"];
+        assert!(!key.modelled);
+    }
+}
@@ -0,0 +1,95 @@
+//! Deserializing entities with keys the data model doesn't know.
+//!
+//! The structs of [`crate::data`] deny unknown fields, so instead of relaxing them, an entity is
+//! deserialized through a [`Tracked`] deserializer that remembers the path of the map it failed
+//! in. When that fails on an unknown field, the key is removed from the value and deserialization
+//! is retried.
+
+use crate::{
+    span::{format_path, PathSegment},
+    track::{At, Hook, Tracked},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// What to do with keys the data model doesn't know.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownFields {
+    /// Fail to load the entity.
+    #[default]
+    Strict,
+    /// Load the entity without them, but keep them in [`crate::compendium::Sourced::extra`].
+    Capture,
+    /// Load the entity without them.
+    Ignore,
+}
+
+/// The unknown keys of an entity by their path inside it, like `slots[0].note`.
+pub type Extra = BTreeMap<String, Value>;
+
+/// Deserialize `value` as `T`, removing (and returning) every unknown field on the way.
+pub fn from_value_lenient<T: DeserializeOwned>(mut value: Value) -> serde_json::Result<(T, Extra)> {
+    let (t, unknown) = strip_unknown(&mut value)?;
+    let extra = unknown
+        .into_iter()
+        .map(|(path, value)| (format_path(&path), value))
+        .collect();
+    Ok((t, extra))
+}
+
+/// Unknown fields with their paths, in the order they were found.
+pub(crate) type Unknown = Vec<(Vec<PathSegment>, Value)>;
+
+/// Deserialize `value` as `T`, removing every unknown field from it.
+pub(crate) fn strip_unknown<T: DeserializeOwned>(
+    value: &mut Value,
+) -> serde_json::Result<(T, Unknown)> {
+    let mut unknown = Vec::new();
+    loop {
+        let context = Context::default();
+        let err = match T::deserialize(Tracked::new(value.clone(), &context)) {
+            Ok(t) => return Ok((t, unknown)),
+            Err(err) => err,
+        };
+
+        let Some((mut path, key)) = context.failed.into_inner() else {
+            return Err(err);
+        };
+        if !err.to_string().starts_with("unknown field") {
+            return Err(err);
+        }
+        let Some(removed) = remove(value, &path, &key) else {
+            return Err(err);
+        };
+        path.push(PathSegment::Key(key));
+        unknown.push((path, removed));
+    }
+}
+
+fn remove(value: &mut Value, path: &[PathSegment], key: &str) -> Option<Value> {
+    let mut value = value;
+    for segment in path {
+        value = match segment {
+            PathSegment::Key(key) => value.as_object_mut()?.get_mut(key)?,
+            PathSegment::Index(index) => value.as_array_mut()?.get_mut(*index)?,
+        };
+    }
+    value.as_object_mut()?.shift_remove(key)
+}
+
+/// Remembers the map and key of an unknown field.
+#[derive(Default)]
+struct Context {
+    /// The path of the map and the key whose deserialization failed.
+    failed: RefCell<Option<(Vec<PathSegment>, String)>>,
+}
+
+impl Hook for Context {
+    fn failed(&self, at: At<'_>, key: Option<&str>) {
+        if let Some(key) = key {
+            *self.failed.borrow_mut() = Some((at.path.to_vec(), key.to_owned()));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! A "where used" index from every id to the places that refer to it.
+//!
+//! Build it from the compendium as loaded: after [`crate::inherit::resolve_inheritance`], every
+//! child entity would be reported for the references it only inherits.
+
+use crate::{
+    compendium::{Compendium, SourceFile},
+    data::EntityKind,
+    refs::{collect_references, Reference},
+    span::{Location, Span},
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+/// A single place that refers to an id.
+#[derive(Debug, Clone, Serialize)]
+pub struct Usage<'a> {
+    pub kind: EntityKind,
+    pub id: &'a str,
+    /// The field path inside the entity, like `slots[0].forbidden`.
+    pub path: String,
+    /// What kind of entity the id is used as.
+    pub as_kind: EntityKind,
+    pub source: &'a SourceFile,
+    pub span: Option<Span>,
+}
+
+impl Usage<'_> {
+    /// The path including the entity, like `elements[y].slots[0].forbidden`.
+    pub fn full_path(&self) -> String {
+        format!("{}[{}].{}", self.kind, self.id, self.path)
+    }
+}
+
+impl Display for Usage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) in {}",
+            self.full_path(),
+            self.as_kind,
+            Location(self.source, self.span)
+        )
+    }
+}
+
+impl<'a> From<Reference<'a>> for Usage<'a> {
+    fn from(reference: Reference<'a>) -> Self {
+        Self {
+            kind: reference.from_kind,
+            id: reference.from_id,
+            path: reference.path,
+            as_kind: reference.to_kind,
+            source: reference.source,
+            span: reference.span,
+        }
+    }
+}
+
+/// Every id and the places it is used, ids of different kinds (like a recipe and an element with
+/// the same id) share an entry.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct UsageIndex<'a> {
+    pub usages: BTreeMap<&'a str, Vec<Usage<'a>>>,
+}
+
+impl<'a> UsageIndex<'a> {
+    pub fn build(compendium: &'a Compendium) -> Self {
+        let mut usages: BTreeMap<&str, Vec<Usage>> = BTreeMap::new();
+        for reference in collect_references(compendium) {
+            usages
+                .entry(reference.to_id)
+                .or_default()
+                .push(reference.into());
+        }
+        Self { usages }
+    }
+
+    /// Every place `id` is used, as any kind.
+    pub fn get(&self, id: &str) -> &[Usage<'a>] {
+        self.usages.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Every place `id` is used as `kind`.
+    pub fn get_kind(&self, kind: EntityKind, id: &str) -> impl Iterator<Item = &Usage<'a>> {
+        self.get(id)
+            .iter()
+            .filter(move |usage| usage.as_kind == kind)
+    }
+
+    /// Every id that is used somewhere.
+    pub fn ids(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.usages.keys().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Data;
+    use serde_json::json;
+
+    #[test]
+    fn usages() {
+        let mut compendium = Compendium::new();
+        let data: Data = serde_json::from_value(json!({
+            "decks": [{ "id": "z", "spec": ["x", "y"] }],
+            "elements": [
+                { "id": "x" },
+                {
+                    "id": "y",
+                    "slots": [{ "id": "s", "actionid": "work", "forbidden": { "x": 1 } }],
+                },
+            ],
+            "recipes": [{ "id": "x", "actionid": "work", "linked": [{ "id": "x" }] }],
+        }))
+        .unwrap();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "test.json".into(),
+        };
+        compendium.insert_data(&source, data);
+
+        let index = UsageIndex::build(&compendium);
+        let paths = |usages: Vec<&Usage>| -> Vec<String> {
+            usages.iter().map(|usage| usage.full_path()).collect()
+        };
+        assert_eq!(
+            paths(index.get("x").iter().collect()),
+            [
+                "decks[z].spec",
+                "elements[y].slots[0].forbidden",
+                "recipes[x].linked[0].id",
+            ]
+        );
+        assert_eq!(
+            paths(index.get_kind(EntityKind::Elements, "x").collect()),
+            ["decks[z].spec", "elements[y].slots[0].forbidden"]
+        );
+        assert_eq!(
+            paths(index.get_kind(EntityKind::Recipes, "x").collect()),
+            ["recipes[x].linked[0].id"]
+        );
+        assert!(index.get("gone").is_empty());
+        assert_eq!(
+            index.get("y")[0].to_string(),
+            "decks[z].spec (elements) in root/test.json"
+        );
+    }
+}
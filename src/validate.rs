@@ -0,0 +1,175 @@
+//! Checks over the loaded content.
+
+use crate::{
+    compendium::{Compendium, SourceFile, Sourced},
+    data::{Entity, EntityKind},
+    refs::{collect_references, Reference},
+    span::{Location, Span},
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+/// A reference to an entity that doesn't exist.
+#[derive(Debug, Clone, Serialize)]
+pub struct DanglingReference {
+    pub kind: EntityKind,
+    pub id: String,
+    /// The field path inside the referring entity.
+    pub path: String,
+    pub source: SourceFile,
+    pub span: Option<Span>,
+    pub target_kind: EntityKind,
+    pub target: String,
+}
+
+impl From<Reference<'_>> for DanglingReference {
+    fn from(reference: Reference<'_>) -> Self {
+        Self {
+            kind: reference.from_kind,
+            id: reference.from_id.to_owned(),
+            path: reference.path,
+            source: reference.source.clone(),
+            span: reference.span,
+            target_kind: reference.to_kind,
+            target: reference.to_id.to_owned(),
+        }
+    }
+}
+
+impl Display for DanglingReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}].{} refers to missing {}[{}]",
+            Location(&self.source, self.span),
+            self.kind,
+            self.id,
+            self.path,
+            self.target_kind,
+            self.target
+        )
+    }
+}
+
+/// Find every reference in `compendium` whose target doesn't exist.
+pub fn dangling_references(compendium: &Compendium) -> Vec<DanglingReference> {
+    collect_references(compendium)
+        .into_iter()
+        .filter(|reference| !compendium.contains(reference.to_kind, reference.to_id))
+        .map(DanglingReference::from)
+        .collect()
+}
+
+/// A key the data model doesn't know, kept by [`crate::unknown::UnknownFields::Capture`].
+#[derive(Debug, Clone, Serialize)]
+pub struct UnknownField {
+    pub kind: EntityKind,
+    pub id: String,
+    /// The field path inside the entity, like `slots[0].note`.
+    pub path: String,
+    pub source: SourceFile,
+    pub span: Option<Span>,
+    pub value: Value,
+}
+
+impl Display for UnknownField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}].{} is not part of the data model",
+            Location(&self.source, self.span),
+            self.kind,
+            self.id,
+            self.path,
+        )
+    }
+}
+
+/// Find every key that was captured while loading `compendium`.
+pub fn unknown_fields(compendium: &Compendium) -> Vec<UnknownField> {
+    let mut out = Vec::new();
+    unknown_fields_of(&mut out, &compendium.achievements);
+    unknown_fields_of(&mut out, &compendium.cultures);
+    unknown_fields_of(&mut out, &compendium.decks);
+    unknown_fields_of(&mut out, &compendium.dicta);
+    unknown_fields_of(&mut out, &compendium.elements);
+    unknown_fields_of(&mut out, &compendium.endings);
+    unknown_fields_of(&mut out, &compendium.legacies);
+    unknown_fields_of(&mut out, &compendium.levers);
+    unknown_fields_of(&mut out, &compendium.portals);
+    unknown_fields_of(&mut out, &compendium.recipes);
+    unknown_fields_of(&mut out, &compendium.settings);
+    unknown_fields_of(&mut out, &compendium.verbs);
+    out
+}
+
+fn unknown_fields_of<T: Entity>(
+    out: &mut Vec<UnknownField>,
+    entities: &BTreeMap<String, Sourced<T>>,
+) {
+    for (id, entity) in entities {
+        out.extend(entity.extra.iter().map(|(path, value)| UnknownField {
+            kind: T::KIND,
+            id: id.clone(),
+            path: path.clone(),
+            source: entity.source.clone(),
+            span: entity.span,
+            value: value.clone(),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compendium::SourceFile, data::Data};
+    use serde_json::json;
+
+    #[test]
+    fn dangling_portal_references() {
+        let data: Data = serde_json::from_value(json!({
+            "portals": [{
+                "id": "wood",
+                "label": "",
+                "description": "",
+                "icon": "",
+                "otherworldid": "mansus",
+                "egressid": "missing.egress",
+                "consequences": [
+                    { "id": "c", "topath": "~/hand", "deckeffects": { "missing.deck": 1 } },
+                ],
+            }],
+        }))
+        .unwrap();
+        let mut compendium = Compendium::new();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "portals.json".into(),
+        };
+        compendium.insert_data(&source, data);
+
+        let dangling: Vec<_> = dangling_references(&compendium)
+            .into_iter()
+            .map(|dangling| (dangling.path, dangling.target_kind, dangling.target))
+            .collect();
+        assert_eq!(
+            dangling,
+            [
+                (
+                    "egressid".to_owned(),
+                    EntityKind::Portals,
+                    "missing.egress".to_owned()
+                ),
+                (
+                    "consequences[0].deckeffects".to_owned(),
+                    EntityKind::Decks,
+                    "missing.deck".to_owned()
+                ),
+            ]
+        );
+    }
+}
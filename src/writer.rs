@@ -0,0 +1,175 @@
+//! Write entities back as content files the game loads.
+//!
+//! Serializing an entity directly uses the name each key is renamed to, and writes every field,
+//! even the ones that were missing and filled in by `#[serde(default)]`. A [`KeyLayout`] remembers
+//! how an entity looked when it was read, so it can be written back with the same keys:
+//!
+//! - Keys keep their original spelling, like `Desc` instead of `description`, and their original
+//!   order. Keys that weren't there before come after them.
+//! - Fields that were missing are left out again, unless they were changed since.
+//! - Unknown fields are written back as they were.
+
+use crate::{
+    data::{Entity, EntityKind},
+    span::PathSegment,
+    unknown::strip_unknown,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, io};
+
+/// The keys of an entity (or a value inside it) as they appeared in its original file.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeyLayout {
+    /// The original spelling of every key by the name it serializes as, and the layout of its
+    /// value.
+    keys: BTreeMap<String, (String, KeyLayout)>,
+    /// The serialized value of every field that was missing.
+    defaults: Map<String, Value>,
+    /// Keys that aren't part of the data model, with their original value.
+    extra: Map<String, Value>,
+    /// Every original key, in the order it appeared in.
+    order: Vec<String>,
+    /// The layout of every item, if this is a list.
+    items: Vec<KeyLayout>,
+}
+
+impl KeyLayout {
+    /// Deserialize `entity` as `T`, remembering its layout.
+    ///
+    /// Unknown fields don't fail deserialization, they are kept in the layout instead. `entity`
+    /// can't use merge operators (see [`crate::merge`]), since it has to deserialize on its own.
+    pub fn read<T: Entity + DeserializeOwned + Serialize>(
+        entity: &Value,
+    ) -> serde_json::Result<(T, Self)> {
+        let mut known = entity.clone();
+        let (value, unknown) = strip_unknown::<T>(&mut known)?;
+        let serialized = serde_json::to_value(&value)?;
+
+        let mut layout = Self::default();
+        layout.build(entity, &serialized, T::ALIASES);
+        for (path, value) in unknown {
+            layout.insert_extra(&path, value);
+        }
+        Ok((value, layout))
+    }
+
+    /// Serialize `value` with this layout.
+    pub fn write<T: Serialize>(&self, value: &T) -> serde_json::Result<Value> {
+        Ok(write_value(serde_json::to_value(value)?, Some(self)))
+    }
+
+    /// Remember the layout of `original`, which serializes as `serialized` once its unknown fields
+    /// are removed.
+    ///
+    /// `aliases` are the [`Entity::ALIASES`] of the struct, only entities have aliases.
+    fn build(&mut self, original: &Value, serialized: &Value, aliases: &[(&str, &str)]) {
+        match (original, serialized) {
+            (Value::Object(original), Value::Object(serialized)) => {
+                self.order = original.keys().cloned().collect();
+                for (key, value) in original {
+                    let name = match serialized.contains_key(key) {
+                        true => key,
+                        false => (aliases.iter())
+                            .find(|(alias, name)| alias == key && serialized.contains_key(*name))
+                            .map_or(key.as_str(), |(_, name)| name),
+                    };
+                    // Unknown fields are added by `insert_extra`
+                    let Some(serialized) = serialized.get(name) else {
+                        continue;
+                    };
+                    let mut layout = Self::default();
+                    layout.build(value, serialized, &[]);
+                    self.keys.insert(name.to_owned(), (key.clone(), layout));
+                }
+                for (name, value) in serialized {
+                    if !self.keys.contains_key(name) {
+                        self.defaults.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+            (Value::Array(original), Value::Array(serialized)) => {
+                for (value, serialized) in original.iter().zip(serialized) {
+                    let mut layout = Self::default();
+                    layout.build(value, serialized, &[]);
+                    self.items.push(layout);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn insert_extra(&mut self, path: &[PathSegment], value: Value) {
+        let Some((PathSegment::Key(key), path)) = path.split_last() else {
+            return;
+        };
+        let mut layout = self;
+        for segment in path {
+            let next = match segment {
+                PathSegment::Key(key) => (layout.keys.values_mut())
+                    .find(|(original, _)| original == key)
+                    .map(|(_, layout)| layout),
+                PathSegment::Index(index) => layout.items.get_mut(*index),
+            };
+            let Some(next) = next else {
+                return;
+            };
+            layout = next;
+        }
+        layout.extra.insert(key.clone(), value);
+    }
+}
+
+fn write_value(value: Value, layout: Option<&KeyLayout>) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut out = Map::new();
+            for (name, value) in map {
+                match layout.and_then(|layout| layout.keys.get(&name)) {
+                    Some((key, layout)) => {
+                        out.insert(key.clone(), write_value(value, Some(layout)));
+                    }
+                    None => {
+                        let default = layout.and_then(|layout| layout.defaults.get(&name));
+                        // Unset options are left out of new values too
+                        if default != Some(&value) && !value.is_null() {
+                            out.insert(name, write_value(value, None));
+                        }
+                    }
+                }
+            }
+            let Some(layout) = layout else {
+                return Value::Object(out);
+            };
+            for (key, value) in &layout.extra {
+                out.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            let mut ordered = Map::new();
+            for key in &layout.order {
+                if let Some(value) = out.shift_remove(key) {
+                    ordered.insert(key.clone(), value);
+                }
+            }
+            ordered.extend(out);
+            Value::Object(ordered)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| write_value(item, layout.and_then(|layout| layout.items.get(i))))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Write a content file with `entities`, which were written with [`KeyLayout::write`].
+///
+/// The entities keep their order, the kinds are written in the order of [`EntityKind`].
+pub fn write_file<W: io::Write>(
+    writer: W,
+    entities: &BTreeMap<EntityKind, Vec<Value>>,
+) -> serde_json::Result<()> {
+    serde_json::to_writer_pretty(writer, entities)
+}
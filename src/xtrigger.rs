@@ -0,0 +1,227 @@
+//! Xtrigger (catalyst) resolution.
+//!
+//! When an element is exposed to a catalyst aspect it has an xtrigger for, every xtrigger for
+//! that catalyst fires with its chance, like when a book is read and turns into a lesson.
+
+use crate::{
+    compendium::{Compendium, Sourced},
+    data::{
+        Elements, ElementsXTriggers, MorphEffect, StringMapOrArray, StringOrStruct, XTriggerLevel,
+        XTriggerTarget,
+    },
+    simulate::Aspects,
+};
+use serde::Serialize;
+use std::fmt::{self, Display};
+
+/// A single xtrigger that fires for a catalyst, with `^` resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Transformation<'a> {
+    pub catalyst: &'a str,
+    pub effect: MorphEffect,
+    /// The element (or aspect for mutations) the effect produces, [`None`] for `""`.
+    pub target: Option<&'a str>,
+    pub level: i32,
+    /// The chance in percent.
+    pub chance: u32,
+    pub additive: bool,
+}
+
+impl Display for Transformation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} x{} ({}%)",
+            self.catalyst,
+            self.effect.as_str(),
+            self.target.unwrap_or("<nothing>"),
+            self.level,
+            self.chance
+        )?;
+        if self.additive {
+            write!(f, " additive")?;
+        }
+        Ok(())
+    }
+}
+
+/// Every transformation of `element` when it is exposed to `catalysts`.
+///
+/// Only catalysts with a positive amount count.
+pub fn transformations<'a>(element: &'a Elements, catalysts: &Aspects) -> Vec<Transformation<'a>> {
+    let mut out = Vec::new();
+    for (catalyst, xtriggers) in element.xtriggers.iter().flatten() {
+        let amount = catalysts.get(catalyst).copied().unwrap_or(0);
+        if amount <= 0 {
+            continue;
+        }
+        match xtriggers {
+            StringMapOrArray::Str(id) => out.push(transform_into(catalyst, id)),
+            StringMapOrArray::Map(xtrigger) => out.push(fire(catalyst, amount, xtrigger)),
+            StringMapOrArray::Arr(xtriggers) => out.extend(
+                xtriggers
+                    .iter()
+                    .map(|xtrigger| fire(catalyst, amount, xtrigger)),
+            ),
+        }
+    }
+    out
+}
+
+fn fire<'a>(
+    catalyst: &'a str,
+    amount: i32,
+    xtrigger: &'a StringOrStruct<ElementsXTriggers>,
+) -> Transformation<'a> {
+    match xtrigger {
+        StringOrStruct::Str(id) => transform_into(catalyst, id),
+        StringOrStruct::Struct(xtrigger) => resolve(catalyst, amount, xtrigger),
+    }
+}
+
+/// The short form of an xtrigger, which is just the id to transform into.
+fn transform_into<'a>(catalyst: &'a str, id: &'a str) -> Transformation<'a> {
+    Transformation {
+        catalyst,
+        effect: MorphEffect::Transform,
+        target: Some(id),
+        level: 1,
+        chance: 100,
+        additive: false,
+    }
+}
+
+fn resolve<'a>(
+    catalyst: &'a str,
+    amount: i32,
+    xtrigger: &'a ElementsXTriggers,
+) -> Transformation<'a> {
+    Transformation {
+        catalyst,
+        effect: xtrigger.effect(),
+        target: match &xtrigger.id {
+            XTriggerTarget::None => None,
+            XTriggerTarget::Catalyst => Some(catalyst),
+            XTriggerTarget::Element(id) => Some(id),
+        },
+        level: match xtrigger.level() {
+            XTriggerLevel::Fixed(level) => level,
+            XTriggerLevel::Catalyst => amount,
+        },
+        chance: xtrigger.chance,
+        additive: xtrigger.additive,
+    }
+}
+
+/// Every element with an xtrigger for `catalyst`, together with what it turns into.
+pub fn catalysed_by<'a>(
+    compendium: &'a Compendium,
+    catalyst: &str,
+) -> Vec<(&'a Sourced<Elements>, Vec<Transformation<'a>>)> {
+    let catalysts = Aspects::from([(catalyst.to_owned(), 1)]);
+    compendium
+        .elements
+        .values()
+        .map(|element| (element, transformations(element, &catalysts)))
+        .filter(|(_, transformations)| !transformations.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compendium::SourceFile, data::Data};
+    use serde_json::json;
+
+    fn element(value: serde_json::Value) -> Elements {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn catalysts(catalysts: &[(&str, i32)]) -> Aspects {
+        (catalysts.iter())
+            .map(|&(aspect, amount)| (aspect.to_owned(), amount))
+            .collect()
+    }
+
+    #[test]
+    fn short_form() {
+        let book = element(json!({ "id": "book", "xtriggers": { "reading": "lesson" } }));
+        assert_eq!(
+            transformations(&book, &catalysts(&[("reading", 1)])),
+            [Transformation {
+                catalyst: "reading",
+                effect: MorphEffect::Transform,
+                target: Some("lesson"),
+                level: 1,
+                chance: 100,
+                additive: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn catalyst_target_and_level() {
+        let candle = element(json!({
+            "id": "candle",
+            "xtriggers": {
+                "fire": [
+                    { "id": "^", "morpheffect": "mutate", "level": "^" },
+                    { "id": "ash", "morpheffect": "spawn", "chance": 30, "additive": true },
+                    { "id": "", "morpheffect": "setmutation", "level": -2 },
+                    "wax",
+                ],
+            },
+        }));
+        let transformations = transformations(&candle, &catalysts(&[("fire", 3)]));
+        let summary: Vec<_> = (transformations.iter()).map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            [
+                "fire: mutate fire x3 (100%)",
+                "fire: spawn ash x1 (30%) additive",
+                "fire: setmutation <nothing> x-2 (100%)",
+                "fire: transform wax x1 (100%)",
+            ]
+        );
+    }
+
+    #[test]
+    fn absent_catalysts() {
+        let book = element(json!({
+            "id": "book",
+            "xtriggers": { "reading": "lesson", "fire": "ash", "damp": "mould" },
+        }));
+        let catalysts = catalysts(&[("reading", 1), ("fire", 0), ("damp", -1)]);
+        let targets: Vec<_> = (transformations(&book, &catalysts).into_iter())
+            .map(|transformation| transformation.target)
+            .collect();
+        assert_eq!(targets, [Some("lesson")]);
+    }
+
+    #[test]
+    fn catalysed_by_catalyst() {
+        let mut compendium = Compendium::new();
+        let data: Data = serde_json::from_value(json!({
+            "elements": [
+                { "id": "book", "xtriggers": { "reading": "lesson" } },
+                { "id": "candle", "xtriggers": { "fire": "ash" } },
+                { "id": "lesson" },
+                { "id": "scroll", "xtriggers": { "reading": [{ "id": "^", "level": "^" }] } },
+            ],
+        }))
+        .unwrap();
+        let source = SourceFile {
+            root: "root".into(),
+            path: "test.json".into(),
+        };
+        compendium.insert_data(&source, data);
+
+        let catalysed: Vec<_> = (catalysed_by(&compendium, "reading").into_iter())
+            .map(|(element, transformations)| (element.id.as_str(), transformations[0].target))
+            .collect();
+        assert_eq!(
+            catalysed,
+            [("book", Some("lesson")), ("scroll", Some("reading"))]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Content files read with [`KeyLayout::read`] and written back with [`KeyLayout::write`] and
+//! [`write_file`] have to come out as they went in.
+
+use research_assistant::{
+    data::{
+        Achievements, Cultures, Decks, Dicta, Elements, Endings, Entity, EntityKind, Legacies,
+        Levers, Portals, Recipes, Settings, Verbs,
+    },
+    writer::{write_file, KeyLayout},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+fn fixture(name: &str) -> String {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests/fixtures/round_trip",
+        name,
+    ]
+    .iter()
+    .collect();
+    fs::read_to_string(path).unwrap()
+}
+
+fn round_trip_as<T: Entity + DeserializeOwned + Serialize>(entity: &Value) -> Value {
+    let (value, layout) = KeyLayout::read::<T>(entity).unwrap();
+    layout.write(&value).unwrap()
+}
+
+fn round_trip(kind: EntityKind, entity: &Value) -> Value {
+    match kind {
+        EntityKind::Achievements => round_trip_as::<Achievements>(entity),
+        EntityKind::Cultures => round_trip_as::<Cultures>(entity),
+        EntityKind::Decks => round_trip_as::<Decks>(entity),
+        EntityKind::Dicta => round_trip_as::<Dicta>(entity),
+        EntityKind::Elements => round_trip_as::<Elements>(entity),
+        EntityKind::Endings => round_trip_as::<Endings>(entity),
+        EntityKind::Legacies => round_trip_as::<Legacies>(entity),
+        EntityKind::Levers => round_trip_as::<Levers>(entity),
+        EntityKind::Portals => round_trip_as::<Portals>(entity),
+        EntityKind::Recipes => round_trip_as::<Recipes>(entity),
+        EntityKind::Settings => round_trip_as::<Settings>(entity),
+        EntityKind::Verbs => round_trip_as::<Verbs>(entity),
+    }
+}
+
+/// Read every entity of the fixture `name`, and write the file again.
+fn round_trip_file(name: &str) -> (String, String) {
+    let text = fixture(name);
+    let data: Map<String, Value> = serde_json::from_str(&text).unwrap();
+    let mut entities: BTreeMap<EntityKind, Vec<Value>> = BTreeMap::new();
+    for (key, values) in &data {
+        let kind: EntityKind = key.parse().unwrap();
+        for value in values.as_array().unwrap() {
+            entities
+                .entry(kind)
+                .or_default()
+                .push(round_trip(kind, value));
+        }
+    }
+    let mut written = Vec::new();
+    write_file(&mut written, &entities).unwrap();
+    (text, String::from_utf8(written).unwrap())
+}
+
+#[test]
+fn elements() {
+    let (text, written) = round_trip_file("elements.json");
+    assert_eq!(written, text.trim_end());
+}
+
+#[test]
+fn recipes_and_verbs() {
+    let (text, written) = round_trip_file("recipes.json");
+    assert_eq!(written, text.trim_end());
+}
+
+#[test]
+fn changed_entity() {
+    let text = fixture("elements.json");
+    let data: Map<String, Value> = serde_json::from_str(&text).unwrap();
+    let hammer = &data["elements"][0];
+
+    let (mut value, layout) = KeyLayout::read::<Elements>(hammer).unwrap();
+    value.label = Some("Mallet".to_owned());
+    value.unique = Some(true);
+    let written = layout.write(&value).unwrap();
+
+    let keys: Vec<&str> = written
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    assert_eq!(
+        keys,
+        ["ID", "Label", "desc", "aspects", "slots", "comment", "unique"]
+    );
+    assert_eq!(written["Label"], "Mallet");
+    assert_eq!(written["slots"][0]["note"], "unknown to the model");
+}